@@ -1,3 +1,4 @@
+use core::sync::atomic::Ordering;
 #[cfg(feature = "defmt")]
 use defmt::{info, warn};
 use embassy_futures::{
@@ -22,12 +23,13 @@ use trouble_host::{
     },
 };
 
-use crate::{BATTERY_LEVEL, MESSAGE_TO_PERI, battery::Battery};
+use crate::{BATTERY_LEVEL, MESSAGE_TO_PERI, PERI_CONNECTED, battery::Battery};
 
 use crate::{
     ble::{ble_task, get_device_address},
     config::PERI_ADDRESS,
     delay_ms,
+    matrix::SPLIT_MESSAGE_LEN,
 };
 
 const CONNECTIONS_MAX: usize = 1;
@@ -98,6 +100,8 @@ pub async fn ble_central_run<RNG, S>(
             )
             .await;
 
+            PERI_CONNECTED.store(false, Ordering::Relaxed);
+
             #[cfg(feature = "defmt")]
             warn!("[ble_connect] peripheral device disconnected");
         }
@@ -153,7 +157,7 @@ async fn kb_tasks<'a>(client: &'a GattClient<'a, SoftdeviceController<'a>, Defau
 
     let service = services.first().unwrap().clone();
 
-    let keyboard_characteristic: Characteristic<[u8; 6]> = client
+    let keyboard_characteristic: Characteristic<[u8; SPLIT_MESSAGE_LEN]> = client
         .characteristic_by_uuid(&service, &Uuid::new_short(0xff22))
         .await
         .expect("[ble_central] unable to set characteristic");
@@ -209,18 +213,19 @@ async fn split_battery_task<'a>(
 /// Split Keyboard service task
 async fn split_keyboard_task<'a>(
     client: &'a GattClient<'a, SoftdeviceController<'a>, DefaultPacketPool, 10>,
-    characteristic: &Characteristic<[u8; 6]>,
+    characteristic: &Characteristic<[u8; SPLIT_MESSAGE_LEN]>,
 ) {
     #[cfg(feature = "defmt")]
     info!("[ble_split_keyboard_task] running split_keyboard_task");
 
-    let mut message_to_peri = MESSAGE_TO_PERI
-        .receiver()
-        .expect(" [ble_peripheral] maximum number of receivers has been reached");
+    // the events queued before the link went down are outdated, the peripheral released the keys
+    // of this half when it lost the link
+    MESSAGE_TO_PERI.clear();
+    PERI_CONNECTED.store(true, Ordering::Relaxed);
 
     loop {
-        // wait till new key_report is received from key_provision
-        let message: [u8; 6] = message_to_peri.changed().await;
+        // wait till new key event is received from key_provision
+        let message: [u8; SPLIT_MESSAGE_LEN] = MESSAGE_TO_PERI.receive().await.to_split_message();
 
         // write to characteristic
         match client
@@ -32,6 +32,7 @@ use crate::peripherals::BlePeri;
 mod central;
 #[cfg(feature = "peripheral")]
 mod peripheral;
+mod report_map;
 mod services;
 
 bind_interrupts!(pub struct Irqs {
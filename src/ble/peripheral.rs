@@ -1,3 +1,4 @@
+use core::sync::atomic::Ordering;
 #[cfg(feature = "defmt")]
 use defmt::{error, info, warn};
 use embassy_futures::join::join;
@@ -7,7 +8,7 @@ use embassy_nrf::{
     Peri,
     peripherals::{P0_04, SAADC},
 };
-use embassy_time::Duration;
+use embassy_time::{Duration, Instant};
 use embedded_storage_async::nor_flash::NorFlash;
 use nrf_sdc::Error;
 use nrf_sdc::SoftdeviceController;
@@ -30,15 +31,16 @@ use crate::battery::Battery;
 use crate::ble::ble_task;
 use crate::ble::get_device_address;
 use crate::ble::services::SPLIT_SERVICE;
-use crate::config::{BLE_NAME, COLS, MATRIX_KEYS_BUFFER, SPLIT_PERIPHERAL};
-use crate::matrix::KeyPos;
+use crate::config::{BLE_NAME, SPLIT_PERIPHERAL};
+use crate::engine::{HidReport, NKRO_REPORT_LEN};
+use crate::matrix::KeyEvent;
 use crate::storage::{load_bonding_info, store_bonding_info};
-use crate::{BATTERY_LEVEL, MATRIX_KEYS_SPLIT};
+use crate::{BATTERY_LEVEL, HID_REPORTS, HOST_CONNECTED, KEY_EVENTS, SPLIT_DISCONNECTED};
 
 use ssmarshal::{self, serialize};
 
 use crate::ble::services::Server;
-use crate::{KEY_REPORT, delay_ms};
+use crate::delay_ms;
 
 const CONNECTIONS_MAX: usize = SPLIT_PERIPHERAL as usize + 1;
 
@@ -138,13 +140,15 @@ pub async fn ble_peripheral_run<RNG, S>(
                                                 &mut bond_stored,
                                             ),
                                             battery_service_task(&conn_2, &server),
-                                            hid_kb_service_task(&conn_2, &server),
+                                            hid_service_task(&conn_2, &server),
                                             set_conn_params(&conn_2, stack),
                                         );
 
                                         let _ =
                                             select(battery_level_sense.approximate(), comm_tasks)
                                                 .await;
+
+                                        HOST_CONNECTED.store(false, Ordering::Relaxed);
                                     }
                                     Err(_e) => {
                                         #[cfg(feature = "defmt")]
@@ -156,6 +160,10 @@ pub async fn ble_peripheral_run<RNG, S>(
                         })
                         .await;
 
+                        // the hid connection ended with the split one
+                        HOST_CONNECTED.store(false, Ordering::Relaxed);
+                        SPLIT_DISCONNECTED.signal(());
+
                         #[cfg(feature = "defmt")]
                         warn!("[split_adv] task ended");
                     }
@@ -283,8 +291,6 @@ async fn gatt_split_events_handler<'stack, 'server>(
     let split_service_registered_keys = server.split_service.registered_keys;
     let split_service_battery_level = server.split_service.level;
 
-    let matrix_keys_split_sender = MATRIX_KEYS_SPLIT.sender();
-    let mut matrix_keys_split_local = [KeyPos::default(); MATRIX_KEYS_BUFFER];
     let battery_level_sender = BATTERY_LEVEL.sender();
 
     let _reason = loop {
@@ -319,28 +325,15 @@ async fn gatt_split_events_handler<'stack, 'server>(
                     }
                     GattEvent::Write(event) => {
                         if event.handle() == split_service_registered_keys.handle {
-                            // central message to peripheral
-                            let central_data = event.data();
-
-                            // store the central keys in matrix keys
-                            for (index, combined_key) in central_data.iter().enumerate() {
-                                if *combined_key != 255u8 {
-                                    let col = (combined_key & 0x0f) + COLS as u8;
-                                    let row = combined_key >> 4;
-
-                                    matrix_keys_split_local[index] = KeyPos { row, col };
-                                } else {
-                                    matrix_keys_split_local[index] = KeyPos::default();
-                                }
+                            // central key event to peripheral
+                            if let Some(key_event) =
+                                KeyEvent::from_split_message(event.data(), Instant::now())
+                            {
+                                #[cfg(feature = "defmt")]
+                                info!("[split_registered_keys] received: {:?}", key_event);
+
+                                KEY_EVENTS.send(key_event).await;
                             }
-                            // send the new matrix_keys
-                            matrix_keys_split_sender.send(matrix_keys_split_local);
-
-                            #[cfg(feature = "defmt")]
-                            info!(
-                                "[split_registered_keys] received: {:?}",
-                                matrix_keys_split_local
-                            );
                         }
 
                         // split battery level information
@@ -592,31 +585,62 @@ async fn battery_service_task<'stack, 'server>(
     }
 }
 
-/// Keyboard serivce task
-async fn hid_kb_service_task<'stack, 'server>(
+/// Hid serivce task, notifies the queued reports in order
+async fn hid_service_task<'stack, 'server>(
     conn: &GattConnection<'stack, 'server, DefaultPacketPool>,
     server: &'server Server<'_>,
 ) {
-    let mut buff = [0u8; 8];
+    // nothing is queued while not connected, but a report left over from the last connection
+    HID_REPORTS.clear();
+    HOST_CONNECTED.store(true, Ordering::Relaxed);
 
-    let mut key_report = KEY_REPORT
-        .receiver()
-        .expect(" [ble_peripheral] maximum number of receivers has been reached");
+    // the host starts with all keys released, the key provision queues the changes from there
+    let mut released = HidReport::released().into_iter();
 
     loop {
-        // wait till new key_report is received from key_provision
-        let key_report = key_report.changed().await;
-
-        let _n = serialize(&mut buff, &key_report).unwrap();
+        // wait till new report is received from key_provision
+        let report = match released.next() {
+            Some(report) => report,
+            None => HID_REPORTS.receive().await,
+        };
+
+        let hid_service = &server.hid_service;
+        let notified = match report {
+            HidReport::Keyboard(report) => {
+                let mut buff = [0u8; 8];
+                let _n = serialize(&mut buff, &report).unwrap();
+                hid_service.report.notify(conn, &buff).await
+            }
+            HidReport::Nkro(report) => {
+                let mut buff = [0u8; NKRO_REPORT_LEN];
+                let _n = serialize(&mut buff, &report).unwrap();
+                hid_service.nkro_report.notify(conn, &buff).await
+            }
+            HidReport::Mouse(report) => {
+                let mut buff = [0u8; 5];
+                let _n = serialize(&mut buff, &report).unwrap();
+                hid_service.mouse_report.notify(conn, &buff).await
+            }
+            HidReport::Consumer(report) => {
+                let mut buff = [0u8; 2];
+                let _n = serialize(&mut buff, &report).unwrap();
+                hid_service.consumer_report.notify(conn, &buff).await
+            }
+            HidReport::System(report) => {
+                let mut buff = [0u8; 1];
+                let _n = serialize(&mut buff, &report).unwrap();
+                hid_service.system_report.notify(conn, &buff).await
+            }
+        };
 
-        match server.hid_service.report.notify(conn, &buff).await {
+        match notified {
             Ok(_) => {
                 #[cfg(feature = "defmt")]
-                info!("[notify] input keyboard notified successfully")
+                info!("[notify] input report notified successfully")
             }
             Err(_e) => {
                 #[cfg(feature = "defmt")]
-                info!("[notify] input keyboard error: {}", _e);
+                info!("[notify] input report error: {}", _e);
                 break;
             }
         }
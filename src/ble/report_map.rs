@@ -0,0 +1,133 @@
+use crate::engine::NKRO_USAGES;
+
+/// Report id of the keyboard input and led output reports
+pub const KEYBOARD_REPORT_ID: u8 = 0x01;
+/// Report id of the mouse input report
+pub const MOUSE_REPORT_ID: u8 = 0x02;
+/// Report id of the consumer control input report
+pub const CONSUMER_REPORT_ID: u8 = 0x03;
+/// Report id of the system control input report
+pub const SYSTEM_REPORT_ID: u8 = 0x04;
+/// Report id of the n-key rollover keyboard input report
+pub const NKRO_REPORT_ID: u8 = 0x05;
+
+/// Report reference type of an input report
+pub const INPUT_REPORT: u8 = 0x01;
+/// Report reference type of an output report
+pub const OUTPUT_REPORT: u8 = 0x02;
+
+/// Combined hid report map of all reports, each report has its own report id
+#[rustfmt::skip]
+pub const REPORT_MAP: &[u8] = &[
+    // keyboard, same layout as `usbd_hid::descriptor::KeyboardReport`
+    0x05, 0x01, // usage page (generic desktop)
+    0x09, 0x06, // usage (keyboard)
+    0xA1, 0x01, // collection (application)
+    0x85, KEYBOARD_REPORT_ID, // report id
+    0x05, 0x07, // usage page (keyboard)
+    0x19, 0xE0, // usage minimum (left control)
+    0x29, 0xE7, // usage maximum (right gui)
+    0x15, 0x00, // logical minimum (0)
+    0x25, 0x01, // logical maximum (1)
+    0x75, 0x01, // report size (1)
+    0x95, 0x08, // report count (8)
+    0x81, 0x02, // input (data, variable, absolute), modifier byte
+    0x75, 0x08, // report size (8)
+    0x95, 0x01, // report count (1)
+    0x81, 0x01, // input (constant), reserved byte
+    0x05, 0x08, // usage page (leds)
+    0x19, 0x01, // usage minimum (num lock)
+    0x29, 0x05, // usage maximum (kana)
+    0x75, 0x01, // report size (1)
+    0x95, 0x05, // report count (5)
+    0x91, 0x02, // output (data, variable, absolute), led report
+    0x75, 0x03, // report size (3)
+    0x95, 0x01, // report count (1)
+    0x91, 0x01, // output (constant), led report padding
+    0x05, 0x07, // usage page (keyboard)
+    0x19, 0x00, // usage minimum (0)
+    0x29, 0xFF, // usage maximum (255)
+    0x15, 0x00, // logical minimum (0)
+    0x26, 0xFF, 0x00, // logical maximum (255)
+    0x75, 0x08, // report size (8)
+    0x95, 0x06, // report count (6)
+    0x81, 0x00, // input (data, array), key arrays (6 bytes)
+    0xC0, // end collection
+    // mouse, same layout as `usbd_hid::descriptor::MouseReport`
+    0x05, 0x01, // usage page (generic desktop)
+    0x09, 0x02, // usage (mouse)
+    0xA1, 0x01, // collection (application)
+    0x85, MOUSE_REPORT_ID, // report id
+    0x09, 0x01, // usage (pointer)
+    0xA1, 0x00, // collection (physical)
+    0x05, 0x09, // usage page (buttons)
+    0x19, 0x01, // usage minimum (button 1)
+    0x29, 0x08, // usage maximum (button 8)
+    0x15, 0x00, // logical minimum (0)
+    0x25, 0x01, // logical maximum (1)
+    0x75, 0x01, // report size (1)
+    0x95, 0x08, // report count (8)
+    0x81, 0x02, // input (data, variable, absolute), buttons
+    0x05, 0x01, // usage page (generic desktop)
+    0x09, 0x30, // usage (x)
+    0x09, 0x31, // usage (y)
+    0x09, 0x38, // usage (wheel)
+    0x15, 0x81, // logical minimum (-127)
+    0x25, 0x7F, // logical maximum (127)
+    0x75, 0x08, // report size (8)
+    0x95, 0x03, // report count (3)
+    0x81, 0x06, // input (data, variable, relative), x, y and wheel
+    0x05, 0x0C, // usage page (consumer)
+    0x0A, 0x38, 0x02, // usage (ac pan)
+    0x95, 0x01, // report count (1)
+    0x81, 0x06, // input (data, variable, relative), pan
+    0xC0, // end collection
+    0xC0, // end collection
+    // consumer control, same layout as `usbd_hid::descriptor::MediaKeyboardReport`
+    0x05, 0x0C, // usage page (consumer)
+    0x09, 0x01, // usage (consumer control)
+    0xA1, 0x01, // collection (application)
+    0x85, CONSUMER_REPORT_ID, // report id
+    0x15, 0x00, // logical minimum (0)
+    0x26, 0xFF, 0x03, // logical maximum (1023)
+    0x19, 0x00, // usage minimum (0)
+    0x2A, 0xFF, 0x03, // usage maximum (1023)
+    0x75, 0x10, // report size (16)
+    0x95, 0x01, // report count (1)
+    0x81, 0x00, // input (data, array), consumer usage
+    0xC0, // end collection
+    // system control, same layout as `usbd_hid::descriptor::SystemControlReport`
+    0x05, 0x01, // usage page (generic desktop)
+    0x09, 0x80, // usage (system control)
+    0xA1, 0x01, // collection (application)
+    0x85, SYSTEM_REPORT_ID, // report id
+    0x16, 0x81, 0x00, // logical minimum (0x81)
+    0x26, 0xB7, 0x00, // logical maximum (0xB7)
+    0x19, 0x81, // usage minimum (system power down)
+    0x29, 0xB7, // usage maximum (system display lcd autoscale)
+    0x75, 0x08, // report size (8)
+    0x95, 0x01, // report count (1)
+    0x81, 0x40, // input (data, array, null state), system usage
+    0xC0, // end collection
+    // n-key rollover keyboard, same layout as `engine::NkroReport`
+    0x05, 0x01, // usage page (generic desktop)
+    0x09, 0x06, // usage (keyboard)
+    0xA1, 0x01, // collection (application)
+    0x85, NKRO_REPORT_ID, // report id
+    0x05, 0x07, // usage page (keyboard)
+    0x19, 0xE0, // usage minimum (left control)
+    0x29, 0xE7, // usage maximum (right gui)
+    0x15, 0x00, // logical minimum (0)
+    0x25, 0x01, // logical maximum (1)
+    0x75, 0x01, // report size (1)
+    0x95, 0x08, // report count (8)
+    0x81, 0x02, // input (data, variable, absolute), modifier byte
+    0x19, 0x00, // usage minimum (0)
+    0x29, NKRO_USAGES as u8 - 1, // usage maximum
+    0x95, NKRO_USAGES as u8, // report count, one bit per key
+    0x81, 0x02, // input (data, variable, absolute), key bitmap
+    0xC0, // end collection
+];
+
+/// Length of the combined hid report map
+pub const REPORT_MAP_LEN: usize = REPORT_MAP.len();
@@ -2,7 +2,15 @@ use trouble_host::prelude::{
     characteristic::{BATTERY_LEVEL, BATTERY_LEVEL_STATUS},
     *,
 };
-use usbd_hid::descriptor::{KeyboardReport, SerializedDescriptor};
+
+use crate::{
+    ble::report_map::{
+        CONSUMER_REPORT_ID, INPUT_REPORT, KEYBOARD_REPORT_ID, MOUSE_REPORT_ID, NKRO_REPORT_ID,
+        OUTPUT_REPORT, REPORT_MAP, REPORT_MAP_LEN, SYSTEM_REPORT_ID,
+    },
+    engine::NKRO_REPORT_LEN,
+    matrix::SPLIT_MESSAGE_LEN,
+};
 
 /// Custom service for the split device
 pub const SPLIT_SERVICE: BluetoothUuid16 = BluetoothUuid16::new(0xff11);
@@ -31,24 +39,36 @@ pub(crate) struct BatteryService {
 pub(crate) struct HidService {
     #[characteristic(uuid = "2a4a", read, value = [0x01, 0x01, 0x00, 0x03])]
     pub(crate) hid_info: [u8; 4],
-    #[characteristic(uuid = "2a4b", read, value = KeyboardReport::desc().try_into().expect("Failed to convert KeyboardReport to [u8; 69]"))]
-    pub(crate) report_map: [u8; 69],
+    #[characteristic(uuid = "2a4b", read, value = REPORT_MAP.try_into().expect("Failed to convert REPORT_MAP to [u8; REPORT_MAP_LEN]"))]
+    pub(crate) report_map: [u8; REPORT_MAP_LEN],
     #[characteristic(uuid = "2a4c", write_without_response)]
     pub(crate) hid_control_point: u8,
     #[characteristic(uuid = "2a4e", read, write_without_response, value = 1)]
     pub(crate) protocol_mode: u8,
-    #[descriptor(uuid = "2908", read, value = [0u8, 1u8])]
+    #[descriptor(uuid = "2908", read, value = [KEYBOARD_REPORT_ID, INPUT_REPORT])]
     #[characteristic(uuid = "2a4d", read, notify)]
     pub(crate) report: [u8; 8],
-    #[descriptor(uuid = "2908", read, value = [0u8, 2u8])]
+    #[descriptor(uuid = "2908", read, value = [KEYBOARD_REPORT_ID, OUTPUT_REPORT])]
     #[characteristic(uuid = "2a4d", read, write, write_without_response)]
     pub(crate) output_keyboard: [u8; 1],
+    #[descriptor(uuid = "2908", read, value = [MOUSE_REPORT_ID, INPUT_REPORT])]
+    #[characteristic(uuid = "2a4d", read, notify)]
+    pub(crate) mouse_report: [u8; 5],
+    #[descriptor(uuid = "2908", read, value = [CONSUMER_REPORT_ID, INPUT_REPORT])]
+    #[characteristic(uuid = "2a4d", read, notify)]
+    pub(crate) consumer_report: [u8; 2],
+    #[descriptor(uuid = "2908", read, value = [SYSTEM_REPORT_ID, INPUT_REPORT])]
+    #[characteristic(uuid = "2a4d", read, notify)]
+    pub(crate) system_report: [u8; 1],
+    #[descriptor(uuid = "2908", read, value = [NKRO_REPORT_ID, INPUT_REPORT])]
+    #[characteristic(uuid = "2a4d", read, notify)]
+    pub(crate) nkro_report: [u8; NKRO_REPORT_LEN],
 }
 
 #[gatt_service(uuid = SPLIT_SERVICE)]
 pub(crate) struct SplitService {
     #[characteristic(uuid = SPLIT_REPORT_CH, read, notify)]
-    pub(crate) registered_keys: [u8; 6],
+    pub(crate) registered_keys: [u8; SPLIT_MESSAGE_LEN],
     #[descriptor(uuid = descriptors::VALID_RANGE, read, value = [0, 100])]
     #[descriptor(uuid = descriptors::MEASUREMENT_DESCRIPTION, name = "battery_level", read, value = "Battery Level")]
     #[characteristic(uuid = SPLIT_BATTERY_CH, read, notify, value = 0)]
@@ -0,0 +1,55 @@
+use crate::{keycodes::KC, matrix::KeyPos};
+use embassy_time::Duration;
+
+/// Layer mask of a combo active on all layers
+pub const ALL_LAYERS: u32 = u32::MAX;
+
+/// Output of a combo or a leader sequence
+#[derive(Debug, Clone, Copy)]
+pub enum ComboAction {
+    /// Single keycode, can be any key type (e.g. a layer key)
+    Key(KC),
+    /// Keys pressed together, e.g. `&[KC::LCtrl, KC::Backspace]`
+    Chord(&'static [KC]),
+}
+
+impl ComboAction {
+    /// Keycodes of the output
+    pub fn keys(&self) -> &[KC] {
+        match self {
+            ComboAction::Key(kc) => core::slice::from_ref(kc),
+            ComboAction::Chord(kcs) => kcs,
+        }
+    }
+}
+
+/// Decides when the combo output is released
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ComboRelease {
+    /// Release the output as soon as one of the combo keys is released
+    AnyReleased,
+    /// Keep the output pressed until all combo keys are released
+    AllReleased,
+}
+
+/// Keys pressed together at the given positions produce the combo output.
+/// A combo key released before the combo completes cancels it, the held back keys are sent
+/// as plain keys in the order they were pressed
+#[derive(Debug, Clone, Copy)]
+pub struct Combo {
+    /// Matrix positions of the combo keys, split cols are offset by `COLS`
+    pub keys: &'static [KeyPos],
+    pub action: ComboAction,
+    /// All combo keys have to be pressed within this period
+    pub timeout: Duration,
+    /// Bitmask of the layers the combo is active on
+    pub layers: u32,
+    pub release: ComboRelease,
+}
+
+impl Combo {
+    /// Keycodes of the combo output
+    pub fn output(&self) -> &[KC] {
+        self.action.keys()
+    }
+}
@@ -1,4 +1,11 @@
-use crate::keycodes::KC;
+use crate::{
+    combo::{ALL_LAYERS, Combo, ComboAction},
+    key_override::KeyOverride,
+    keycodes::{HoldFlavor, KC, LS, TapDance, TapHold},
+    layer::LayerCondition,
+    leader::LeaderSequence,
+    macros::MacroStep,
+};
 use embassy_time::Duration;
 
 /// Name your keyboard
@@ -11,7 +18,7 @@ pub const ROWS: usize = 4;
 pub const COLS: usize = 5;
 
 /// KeyMap total Layers
-pub const LAYERS: usize = 2;
+pub const LAYERS: usize = 4;
 
 /// Specify if the keyboard is split
 pub const SPLIT_PERIPHERAL: bool = true;
@@ -24,9 +31,9 @@ pub const PERI_ADDRESS: [u8; 6] = [0x0c, 0x4d, 0x2e, 0xb4, 0x1d, 0xfb]; // CREAM
 //
 //X \ Y|  0  |  1  |  2  |  3  |  4  |           X \ Y|  5  |  6  |  7  |  8  |  9  |
 //   0 |__'__|__,__|__.__|__p__|__y__|              0 |__f__|__g__|__c__|__r__|__l__|
-//   1 |__a__|__o__|__e__|__u__|__i__|              1 |__d__|__h__|__t__|__n__|__s__|
+//   1 |__a__|__o__|e/GUI|__u__|__i__|              1 |__d__|__h__|__t__|__n__|__s__|
 //   2 |_CTL_|__q__|__j__|__k__|__x__|              2 |__b__|__m__|__w__|__v__|__z__|
-//   3             |SUPER|SPACE|SHIFT|              3 |_TAB_|ENTER|LYR_1|
+//   3             |LYR_2|SPACE|SHIFT|              3 |_TAB_|ENTER|LYR_1|
 //
 //***********************************************************************************
 // LAYER
@@ -34,7 +41,23 @@ pub const PERI_ADDRESS: [u8; 6] = [0x0c, 0x4d, 0x2e, 0xb4, 0x1d, 0xfb]; // CREAM
 //   0 |_ESC_|__7__|__8__|__9__|_PScr|              0 |_SLCK|__(__|__)__|__\__|__/__|
 //   1 |_BSP_|__4__|__5__|__6__|_DEL_|              1 |__-__|_left|_down|__up_|right|
 //   2 |__0__|__1__|__2__|__3__|_ALT_|              2 |__=__|__[__|__]__|__`__|__;__|
-//   3             |SUPER|SPACE|SHIFT|              3 |_TAB_|ENTER|LYR_1|
+//   3             |_TRN_|LLOCK|_TRN_|              3 |_TRN_|_TRN_|_TRN_|
+//
+//***********************************************************************************
+// LAYER 2: (hold the left thumb)
+//X \ Y|  0  |  1  |  2  |  3  |  4  |           X \ Y|  5  |  6  |  7  |  8  |  9  |
+//   0 |__F1_|__F2_|__F3_|__F4_|__F5_|              0 |__F6_|__F7_|__F8_|__F9_|_F10_|
+//   1 |_F11_|_F12_|_____|_____|_____|              1 |_PREV|_VOL-|_VOL+|_NEXT|_MUTE|
+//   2 |_____|_____|_____|_____|_____|              2 |_PLAY|_STOP|_BRI-|_BRI+|_____|
+//   3             |_TRN_|_TRN_|_TRN_|              3 |_TRN_|_TRN_|_TRN_|
+//
+//***********************************************************************************
+// LAYER 3: (adjust, layer 1 + layer 2)
+//X \ Y|  0  |  1  |  2  |  3  |  4  |           X \ Y|  5  |  6  |  7  |  8  |  9  |
+//   0 |_BTL_|_____|_____|_____|_____|              0 |_____|_____|_____|_____|_____|
+//   1 |_NKRO|_ASFT|_CAPW|_____|_____|              1 |_____|_____|_____|_____|_____|
+//   2 |_SLEP|_WAKE|_____|_____|_____|              2 |_____|_____|_____|_____|_____|
+//   3             |_TRN_|_TRN_|_TRN_|              3 |_TRN_|_TRN_|_TRN_|
 //
 //*****************************************************************************************
 #[rustfmt::skip]
@@ -46,38 +69,233 @@ pub fn provide_keymap() -> [[[KC; KEYMAP_COLS]; ROWS]; LAYERS] {
         /*               +--------------+--------------+---------------+--------------+---------------+        +------------+--------------+-----------+-----------+------------+*/
         /*  ROW 0  */ [/*|*/KC::Quote,/*|*/KC::Comma,/*|*/KC::Period,/*|*/KC::Pp,   /*|*/KC::Yy,    /*|        |*/KC::Ff, /*|*/KC::Gg,   /*|*/KC::Cc,/*|*/KC::Rr,/*|*/KC::Ll ,/*|*/],
         /*               +--------------+--------------+--------------+---------------+---------------+        +------------+--------------+-----------+-----------+------------+*/
-        /*  ROW 1  */ [/*|*/KC::Aa,   /*|*/ KC::Oo,  /*|*/KC::Ee,    /*|*/KC::Uu,   /*|*/KC::Ii,    /*|        |*/KC::Dd, /*|*/KC::Hh,   /*|*/KC::Tt,/*|*/KC::Nn,/*|*/KC::Ss, /*|*/],
+        /*  ROW 1  */ [/*|*/KC::Aa,   /*|*/ KC::Oo,  /*|*/KC::TH3,   /*|*/KC::Uu,   /*|*/KC::Ii,    /*|        |*/KC::Dd, /*|*/KC::Hh,   /*|*/KC::Tt,/*|*/KC::Nn,/*|*/KC::Ss, /*|*/],
         /*               +--------------+--------------+--------------+---------------+---------------+        +------------+--------------+-----------+-----------+------------+*/
         /*  ROW 2  */ [/*|*/KC::LCtrl,/*|*/ KC::Qq,  /*|*/KC::Jj,    /*|*/KC::Kk,   /*|*/KC::Xx,    /*|        |*/KC::Bb, /*|*/KC::Mm,   /*|*/KC::Ww,/*|*/KC::Vv,/*|*/KC::Zz, /*|*/],
         /*               +--------------+--------------+--------------+---------------+---------------+        +------------+--------------+-----------+-----------+------------+*/
-        /*  ROW 3  */ [/*|*/KC::EU,   /*|*/ KC::EU,  /*|*/KC::LGUI,  /*|*/KC::Space,/*|*/KC::LShift,/*|        |*/KC::Tab,/*|*/KC::Enter,/*|*/KC::L1,/*|*/KC::EU,/*|*/KC::EU, /*|*/],
+        /*  ROW 3  */ [/*|*/KC::NoOp,   /*|*/ KC::NoOp,  /*|*/KC::L2,    /*|*/KC::Space,/*|*/KC::LShift,/*|        |*/KC::Tab,/*|*/KC::Enter,/*|*/KC::L1,/*|*/KC::NoOp,/*|*/KC::NoOp, /*|*/],
         /*               +--------------+--------------+--------------+---------------+---------------+        +------------+--------------+-----------+-----------+-----------+*/
     ],
     [
         /* LAYER 1 */  /*       COL 0            COL 1       COL 2         COL 3          COL 4                       COL 5             COL 6                 COL 7              COL 8                 COL 9          */
         /*               +-------------------+-----------+-------------+--------------+---------------+          +--------------+--------------------+---------------------+----------------------+------------------+*/
-        /*  ROW 0  */ [/*|*/ KC::Escape,   /*|*/KC::K7,/*|*/KC::K8,  /*|*/KC::K9,   /*|*/KC::PrintS,/*|          |*/KC::EU,   /*|*/KC::OpenParens, /*|*/KC::CloseParens, /*|*/KC::Bslash,       /*|*/KC::Fslash,   /*|*/],
+        /*  ROW 0  */ [/*|*/ KC::Escape,   /*|*/KC::K7,/*|*/KC::K8,  /*|*/KC::K9,   /*|*/KC::PrintS,/*|          |*/KC::NoOp,   /*|*/LS(KC::K9),     /*|*/LS(KC::K0),      /*|*/KC::Bslash,       /*|*/KC::Fslash,   /*|*/],
         /*               +-------------------+-----------+-------------+--------------+---------------+          +--------------+--------------------+---------------------+----------------------+------------------+*/
         /*  ROW 1  */ [/*|*/ KC::Backspace,/*|*/KC::K4,/*|*/KC::K5,  /*|*/KC::K6,   /*|*/KC::Delete,/*|          |*/KC::Dash, /*|*/KC::LeftArr,    /*|*/KC::DownArr,     /*|*/KC::UpArr,        /*|*/KC::RightArr, /*|*/],
         /*               +-------------------+-----------+-------------+--------------+---------------+          +--------------+--------------------+---------------------+----------------------+------------------+*/
         /*  ROW 2  */ [/*|*/ KC::K0,       /*|*/KC::K1,/*|*/KC::K2,  /*|*/KC::K3,   /*|*/KC::LAlt,  /*|          |*/KC::Equal,/*|*/KC::OpenBracket,/*|*/KC::CloseBracket,/*|*/KC::BacktickTilde,/*|*/KC::SemiColon,/*|*/],
         /*               +-------------------+-----------+-------------+--------------+---------------+          +--------------+--------------------+---------------------+----------------------+------------------+*/
-        /*  ROW 3  */ [/*|*/ KC::EU,       /*|*/KC::EU,/*|*/KC::LGUI,/*|*/KC::Space,/*|*/KC::LShift,/*|          |*/KC::Tab,  /*|*/KC::Enter,      /*|*/KC::L1,          /*|*/KC::EU,           /*|*/KC::EU,       /*|*/],
+        /*  ROW 3  */ [/*|*/ KC::NoOp,       /*|*/KC::NoOp,/*|*/KC::Trans,/*|*/KC::LayerLock,/*|*/KC::Trans, /*|          |*/KC::Trans,/*|*/KC::Trans,      /*|*/KC::Trans,       /*|*/KC::NoOp,           /*|*/KC::NoOp,       /*|*/],
         /*               +-------------------+-----------+-------------+--------------+---------------+          +--------------+--------------------+---------------------+----------------------+------------------+*/
     ],
+    [
+        /* LAYER 2 */  /*   COL 0       COL 1       COL 2       COL 3       COL 4                     COL 5         COL 6         COL 7         COL 8         COL 9     */
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 0  */ [/*|*/KC::F1,  /*|*/KC::F2,  /*|*/KC::F3,  /*|*/KC::F4,  /*|*/KC::F5,  /*|          |*/KC::F6,     /*|*/KC::F7,     /*|*/KC::F8,     /*|*/KC::F9,     /*|*/KC::F10,    /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 1  */ [/*|*/KC::F11, /*|*/KC::F12, /*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|          |*/KC::MePrev, /*|*/KC::MeVolDown,/*|*/KC::MeVolUp,/*|*/KC::MeNext, /*|*/KC::MeMute, /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 2  */ [/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|          |*/KC::MePlay, /*|*/KC::MeStop, /*|*/KC::MeBriDown,/*|*/KC::MeBriUp,/*|*/KC::NoOp,   /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 3  */ [/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::Trans,/*|*/KC::Trans,/*|*/KC::Trans,/*|          |*/KC::Trans,  /*|*/KC::Trans,  /*|*/KC::Trans,  /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+    ],
+    [
+        /* LAYER 3 */  /*   COL 0       COL 1       COL 2       COL 3       COL 4                     COL 5         COL 6         COL 7         COL 8         COL 9     */
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 0  */ [/*|*/KC::BTL, /*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|          |*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 1  */ [/*|*/KC::NkTog,/*|*/KC::AsTog,/*|*/KC::CapsWord,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|          |*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 2  */ [/*|*/KC::SySleep,/*|*/KC::SyWake,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::NoOp,/*|          |*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+        /*  ROW 3  */ [/*|*/KC::NoOp,/*|*/KC::NoOp,/*|*/KC::Trans,/*|*/KC::Trans,/*|*/KC::Trans,/*|          |*/KC::Trans,  /*|*/KC::Trans,  /*|*/KC::Trans,  /*|*/KC::NoOp,   /*|*/KC::NoOp,   /*|*/],
+        /*               +-----------+-----------+-----------+-----------+-----------+          +-------------+-------------+-------------+-------------+-----------+*/
+    ],
 ]}
 
 /// Keymap cols
 pub const KEYMAP_COLS: usize = COLS + (SPLIT_PERIPHERAL as usize * COLS);
 
-/// Size of the registered matrix keys array
-pub const MATRIX_KEYS_BUFFER: usize = 6;
+/// Number of simultaneously pressed keys per half, more keys put the keyboard in error rollover.
+/// Not related to the 6 keys of the hid report, keep it at most 16
+pub const MATRIX_KEYS_BUFFER: usize = 10;
 
 /// Size of the registered matrix keys array for both halfs
 pub const MATRIX_KEYS_COMB_BUFFER: usize = MATRIX_KEYS_BUFFER * 2;
 
+/// Number of key events queued for the key provision (and on the central for the split link)
+pub const KEY_EVENT_QUEUE: usize = 32;
+
+/// Number of hid reports queued for the ble hid service
+pub const HID_REPORT_QUEUE: usize = 32;
+
+/// Start in n-key rollover mode, switched at runtime with `KC::NkOn`, `KC::NkOff` and `KC::NkTog`
+pub const NKRO: bool = false;
+
 /// Wait for a given time before entering sleep in ms
 pub const ENTER_SLEEP_DEBOUNCE: u64 = 600000;
 
 /// Key debounce period
 pub const KEY_DEBOUNCE: Duration = Duration::from_millis(10);
+
+/// Start with auto-shift on, letters and digits held for longer than `AUTO_SHIFT_TERM` are sent shifted.
+/// Switched at runtime with `KC::AsTog`
+pub const AUTO_SHIFT: bool = false;
+
+/// Hold time after which a letter or digit is sent shifted
+pub const AUTO_SHIFT_TERM: Duration = Duration::from_millis(175);
+
+/// Letters and digits that are never auto-shifted
+pub const AUTO_SHIFT_EXCLUDED: &[KC] = &[];
+
+/// Default tapping term of tap-hold keys
+pub const TAPPING_TERM: Duration = Duration::from_millis(200);
+
+/// Period between mouse reports while a mouse move key is held
+pub const MOUSE_MOVE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Period between mouse reports while a mouse wheel key is held
+pub const MOUSE_WHEEL_INTERVAL: Duration = Duration::from_millis(80);
+
+/// Cursor move per mouse report at slow (`KC::MoCS`), normal and fast (`KC::MoCF`) speed
+pub const MOUSE_MOVE_STEPS: [i8; 3] = [2, 8, 20];
+
+/// Wheel move per mouse report at slow (`KC::MoCS`), normal and fast (`KC::MoCF`) speed
+pub const MOUSE_WHEEL_STEPS: [i8; 3] = [1, 1, 3];
+
+/// One-shot modifiers and layers are cancelled when no key is pressed within this period
+pub const ONESHOT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Caps word is turned off when no key is pressed within this period
+pub const CAPS_WORD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The leader sequence ends when no key is pressed within this period after the last one
+pub const LEADER_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Maximum number of keys of a leader sequence
+pub const LEADER_KEYS: usize = 4;
+
+/// Tap-hold keys, used in the keymap as `KC::TH1`..`KC::TH8`
+/// A layer key (`KC::L1`..`KC::L5`) as `hold` makes it a layer-tap key
+pub const TAP_HOLD_KEYS: &[TapHold] = &[
+    // TH1: home row mod, a / ctrl
+    TapHold {
+        tap: KC::Aa,
+        hold: KC::LCtrl,
+        flavor: HoldFlavor::Balanced,
+        tapping_term: TAPPING_TERM,
+    },
+    // TH2: home row mod, o / alt
+    TapHold {
+        tap: KC::Oo,
+        hold: KC::LAlt,
+        flavor: HoldFlavor::Balanced,
+        tapping_term: TAPPING_TERM,
+    },
+    // TH3: home row mod, e / gui
+    TapHold {
+        tap: KC::Ee,
+        hold: KC::LGUI,
+        flavor: HoldFlavor::Balanced,
+        tapping_term: TAPPING_TERM,
+    },
+    // TH4: home row mod, u / shift
+    TapHold {
+        tap: KC::Uu,
+        hold: KC::LShift,
+        flavor: HoldFlavor::Balanced,
+        tapping_term: TAPPING_TERM,
+    },
+    // TH5: layer-tap, space / layer 1
+    TapHold {
+        tap: KC::Space,
+        hold: KC::L1,
+        flavor: HoldFlavor::HoldPreferred,
+        tapping_term: TAPPING_TERM,
+    },
+];
+
+/// Conditional layers, turned on while all their layers are active.
+/// Evaluated in order, so a conditional layer can be the condition of a later rule
+pub const LAYER_CONDITIONS: &[LayerCondition] = &[
+    // tri-layer: layer 1 + layer 2 => layer 3 (adjust)
+    LayerCondition {
+        if_layers: (1 << 1) | (1 << 2),
+        then_layer: 3,
+    },
+];
+
+/// Tap-dance keys, used in the keymap as `KC::TD1`..`KC::TD8`
+/// Unused outputs are `KC::NoOp`
+pub const TAP_DANCE_KEYS: &[TapDance] = &[
+    // TD1: escape / caps lock
+    TapDance {
+        taps: [KC::Escape, KC::CapsLock, KC::NoOp],
+        hold: KC::NoOp,
+        tap_hold: KC::NoOp,
+        tapping_term: TAPPING_TERM,
+    },
+];
+
+/// Default period within which all keys of a combo have to be pressed
+pub const COMBO_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Combos, matched on the key positions of both halves.
+/// The first matching combo wins, list longer combos first
+pub const COMBOS: &[Combo] = &[
+    // e.g. ctrl + d => ctrl + backspace
+    // Combo {
+    //     keys: &[KeyPos { row: 2, col: 0 }, KeyPos { row: 1, col: 5 }],
+    //     action: ComboAction::Chord(&[KC::LCtrl, KC::Backspace]),
+    //     timeout: COMBO_TIMEOUT,
+    //     layers: 1 << 0,
+    //     release: ComboRelease::AllReleased,
+    // },
+];
+
+/// Key overrides, a key pressed with the modifiers sends the replacement without them.
+/// The first matching override wins
+pub const KEY_OVERRIDES: &[KeyOverride] = &[
+    // shift + backspace => delete
+    KeyOverride {
+        mods: &[KC::LShift],
+        key: KC::Backspace,
+        replacement: KC::Delete,
+        layers: ALL_LAYERS,
+    },
+];
+
+/// Leader sequences, tapped after `KC::Leader`.
+/// A sequence that is the start of a longer one is sent after the `LEADER_TIMEOUT`
+pub const LEADER_SEQUENCES: &[LeaderSequence] = &[
+    // leader g c => git commit macro
+    LeaderSequence {
+        keys: &[KC::Gg, KC::Cc],
+        action: ComboAction::Key(KC::MA1),
+    },
+    // leader s => ctrl + s
+    LeaderSequence {
+        keys: &[KC::Ss],
+        action: ComboAction::Chord(&[KC::LCtrl, KC::Ss]),
+    },
+];
+
+/// Alternates sent by `KC::AltRepeat`, the last key is replaced by the other key of its pair
+pub const ALT_REPEAT_KEYS: &[(KC, KC)] = &[
+    (KC::LeftArr, KC::RightArr),
+    (KC::UpArr, KC::DownArr),
+    (KC::Home, KC::End),
+    (KC::PageUp, KC::PageDown),
+    (LS(KC::K9), LS(KC::K0)),
+    (KC::OpenBracket, KC::CloseBracket),
+];
+
+/// Macros, `MA1` plays the first entry, `MA2` the second, ...
+pub const MACROS: &[&[MacroStep]] = &[
+    // git commit -m "" with the cursor between the quotes
+    &[
+        MacroStep::Type("git commit -m \"\""),
+        MacroStep::Tap(KC::LeftArr),
+    ],
+];
@@ -0,0 +1,2633 @@
+use crate::{
+    combo::{Combo, ComboRelease},
+    config::{
+        ALT_REPEAT_KEYS, AUTO_SHIFT, AUTO_SHIFT_EXCLUDED, AUTO_SHIFT_TERM, CAPS_WORD_TIMEOUT, COLS,
+        COMBOS, KEY_OVERRIDES, KEYMAP_COLS, LAYER_CONDITIONS, LAYERS, LEADER_KEYS,
+        LEADER_SEQUENCES, LEADER_TIMEOUT, MACROS, MATRIX_KEYS_BUFFER, MATRIX_KEYS_COMB_BUFFER,
+        MOUSE_MOVE_INTERVAL, MOUSE_MOVE_STEPS, MOUSE_WHEEL_INTERVAL, MOUSE_WHEEL_STEPS, NKRO,
+        ONESHOT_TIMEOUT, ROWS, TAP_DANCE_KEYS, TAP_HOLD_KEYS, provide_keymap,
+    },
+    key_override::KeyOverride,
+    keycodes::{HoldFlavor, KC, KeyType, LS, TapDance, TapHold},
+    layer::LayerCondition,
+    leader::LeaderSequence,
+    macros::{MacroStep, ascii_to_kc},
+    matrix::{Key, KeyEvent, KeyPos, KeyState},
+};
+
+#[cfg(feature = "defmt")]
+use defmt::{Display2Format, info};
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+use serde::Serialize;
+use usbd_hid::descriptor::{KeyboardReport, MediaKeyboardReport, MouseReport, SystemControlReport};
+
+/// Number of keyboard usages in the nkro bitmap (0x00..0x77),
+/// the keys above are sent in the 6kro report
+pub const NKRO_USAGES: usize = 0x78;
+
+/// Size of the serialized nkro report
+pub const NKRO_REPORT_LEN: usize = 1 + NKRO_USAGES / 8;
+
+/// Key that enters the bootloader when held for `BOOTLOADER_HOLD`, as well as `KC::BTL`
+pub const BOOTLOADER_KEY: KeyPos = KeyPos { row: 0, col: 0 };
+
+/// Hold time of the bootloader key
+pub const BOOTLOADER_HOLD: Duration = Duration::from_secs(5);
+
+/// N-key rollover keyboard report, one bit per pressed key
+#[derive(Serialize, PartialEq, Default, Debug, Clone, Copy)]
+pub struct NkroReport {
+    pub modifier: u8,
+    pub keys: [u8; NKRO_USAGES / 8],
+}
+
+/// Report of the combined hid report map
+pub enum HidReport {
+    Keyboard(KeyboardReport),
+    Nkro(NkroReport),
+    Mouse(MouseReport),
+    Consumer(MediaKeyboardReport),
+    System(SystemControlReport),
+}
+
+impl HidReport {
+    /// Reports with every key, button and usage released
+    pub fn released() -> [Self; 5] {
+        [
+            Self::Keyboard(KeyboardReport::default()),
+            Self::Nkro(NkroReport::default()),
+            Self::Mouse(MouseReport {
+                buttons: 0,
+                x: 0,
+                y: 0,
+                wheel: 0,
+                pan: 0,
+            }),
+            Self::Consumer(MediaKeyboardReport { usage_id: 0 }),
+            Self::System(SystemControlReport { usage_id: 0 }),
+        ]
+    }
+}
+
+/// Board the engine runs on, it takes the hid reports and provides the delays
+pub trait Host {
+    /// Send a report, waits while the report queue of a connected host is full and may drop the
+    /// report while no host is connected
+    fn send(&mut self, report: HidReport) -> impl Future<Output = ()>;
+
+    /// Wait for the given duration
+    fn delay(&mut self, duration: Duration) -> impl Future<Output = ()>;
+
+    /// Reboot into the bootloader
+    fn enter_bootloader(&mut self);
+}
+
+/// Keymap and tables the engine runs with, the keyboard takes them from `config`
+pub struct EngineConfig {
+    pub keymap: [[[KC; KEYMAP_COLS]; ROWS]; LAYERS],
+    pub tap_hold_keys: &'static [TapHold],
+    pub tap_dance_keys: &'static [TapDance],
+    pub macros: &'static [&'static [MacroStep]],
+    pub combos: &'static [Combo],
+    pub key_overrides: &'static [KeyOverride],
+    pub leader_sequences: &'static [LeaderSequence],
+    pub layer_conditions: &'static [LayerCondition],
+    pub alt_repeat_keys: &'static [(KC, KC)],
+    pub auto_shift_excluded: &'static [KC],
+    /// Start in nkro mode
+    pub nkro: bool,
+    /// Start with auto-shift on
+    pub auto_shift: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            keymap: provide_keymap(),
+            tap_hold_keys: TAP_HOLD_KEYS,
+            tap_dance_keys: TAP_DANCE_KEYS,
+            macros: MACROS,
+            combos: COMBOS,
+            key_overrides: KEY_OVERRIDES,
+            leader_sequences: LEADER_SEQUENCES,
+            layer_conditions: LAYER_CONDITIONS,
+            alt_repeat_keys: ALT_REPEAT_KEYS,
+            auto_shift_excluded: AUTO_SHIFT_EXCLUDED,
+            nkro: NKRO,
+            auto_shift: AUTO_SHIFT,
+        }
+    }
+}
+
+/// Tap-dance key that is being tapped
+struct ActiveTapDance {
+    tap_dance: &'static TapDance,
+    position: KeyPos,
+    /// Number of presses so far
+    taps: u8,
+    pressed: bool,
+    /// Time of the last press or release
+    time: Instant,
+    /// Output pressed while the key is held after the dance was resolved
+    output: Option<KC>,
+}
+
+/// Combo whose output is currently pressed
+struct ActiveCombo {
+    combo: &'static Combo,
+    /// Bitmask of the matrix_keys_local slots of the combo keys still pressed
+    members: u32,
+}
+
+/// Key processing of the keyboard, key events in and hid reports out.
+/// The time only comes from the key events and `tick`, so it runs the same on the host
+pub struct Engine<H: Host> {
+    host: H,
+    /// Registered keys of both halves, the split keys after the local ones
+    matrix_keys_local: [Key; MATRIX_KEYS_COMB_BUFFER],
+    /// Time of the event or tick being processed
+    now: Instant,
+    /// Bitmask of the active layers on top of the default layer
+    layer_state: u32,
+    /// Bitmask of the layers turned on by the `LAYER_CONDITIONS`
+    condition_layers: u32,
+    default_layer: u8,
+    /// Layer that is turned off after the next key press
+    oneshot_layer: Option<u8>,
+    /// Layers locked on by a double tap of their one-shot key or by `KC::LayerLock`
+    locked_layers: u32,
+    /// Layers of the `locked_layers` locked by `KC::LayerLock`, it only unlocks these
+    layer_lock_layers: u32,
+    /// Momentary layers with the position of the key holding them
+    layer_keys: Vec<(KeyPos, u8), MATRIX_KEYS_COMB_BUFFER>,
+    /// Modifiers of the pressed modifier keys
+    held_mods: u8,
+    /// Modifiers applied to the next key press only
+    oneshot_mods: u8,
+    /// One-shot modifiers locked on by a double tap
+    locked_mods: u8,
+    /// Time when the last one-shot modifier or layer was pressed
+    oneshot_time: Instant,
+    /// Caps word is on
+    caps_word: bool,
+    /// Shift of the caps word, while the last key pressed is a letter or `-`
+    caps_word_mods: u8,
+    /// Time when caps word was turned on or the last word key was pressed
+    caps_word_time: Instant,
+    /// Keys tapped since the leader key, `None` while no leader sequence is running
+    leader: Option<Vec<KC, LEADER_KEYS>>,
+    /// Time when the leader key or the last key of the sequence was pressed
+    leader_time: Instant,
+    /// Last key pressed with the modifiers active at the time, sent again by the repeat keys
+    last_key: Option<KC>,
+    /// Modifiers of the last pressed modified key (e.g. `LS(KC::K9)`)
+    key_mods: u8,
+    /// Key whose press was replaced by a key override
+    override_position: Option<KeyPos>,
+    /// Modifiers of the key override, left out of the report while its replacement is held
+    suppressed_mods: u8,
+    config: EngineConfig,
+    keyreport_local: KeyboardReport,
+    /// Last key report queued for the host
+    keyreport_local_old: KeyboardReport,
+    /// Keys are sent in the nkro report instead of the 6kro report
+    nkro: bool,
+    /// Letters and digits held past the `AUTO_SHIFT_TERM` are sent shifted
+    auto_shift: bool,
+    /// Bitmap of the pressed keys in nkro mode
+    nkro_keys: [u8; NKRO_USAGES / 8],
+    /// Last nkro report queued for the host
+    nkro_report_old: NkroReport,
+    /// Bitmask of the matrix_keys_local slots whose press is held back until a tap-hold key is
+    /// resolved, a key tapped twice has a slot for each press
+    deferred_keys: u32,
+    /// Time when the next pending tap-hold or combo key times out
+    deadline: Option<Instant>,
+    active_combos: Vec<ActiveCombo, MATRIX_KEYS_COMB_BUFFER>,
+    tap_dance: Option<ActiveTapDance>,
+    /// Pressed mouse keys, one bit per mouse keycode
+    mouse_keys: u16,
+    /// Pressed mouse buttons
+    mouse_buttons: u8,
+    /// Usage id of the pressed media key
+    consumer_usage: u16,
+    /// Usage id of the pressed system key
+    system_usage: u8,
+    /// Time of the next mouse report with a cursor move
+    mouse_move_time: Instant,
+    /// Time of the next mouse report with a wheel move
+    mouse_wheel_time: Instant,
+    /// More keys are pressed on the local half than fit in the matrix keys buffer
+    rollover_local: bool,
+    /// More keys are pressed on the split half than fit in the matrix keys buffer
+    rollover_split: bool,
+    /// Pressed keys that found no free slot in their half, the keyboard is in error rollover until
+    /// they are released
+    rollover_keys: Vec<KeyPos, MATRIX_KEYS_COMB_BUFFER>,
+    /// Keys that did not fit in the 6 keycodes of the keyreport_local
+    overflow_keys: Vec<u8, MATRIX_KEYS_COMB_BUFFER>,
+}
+
+impl<H: Host> Engine<H> {
+    pub fn new(host: H) -> Self {
+        Self::with_config(host, EngineConfig::default())
+    }
+
+    pub fn with_config(host: H, config: EngineConfig) -> Self {
+        Self {
+            host,
+            matrix_keys_local: [Key::default(); MATRIX_KEYS_COMB_BUFFER],
+            now: Instant::MIN,
+            layer_state: 0,
+            condition_layers: 0,
+            default_layer: 0,
+            oneshot_layer: None,
+            locked_layers: 0,
+            layer_lock_layers: 0,
+            layer_keys: Vec::new(),
+            held_mods: 0,
+            oneshot_mods: 0,
+            locked_mods: 0,
+            oneshot_time: Instant::MIN,
+            caps_word: false,
+            caps_word_mods: 0,
+            caps_word_time: Instant::MIN,
+            leader: None,
+            leader_time: Instant::MIN,
+            last_key: None,
+            key_mods: 0,
+            override_position: None,
+            suppressed_mods: 0,
+            keyreport_local: KeyboardReport::default(),
+            keyreport_local_old: KeyboardReport::default(),
+            nkro: config.nkro,
+            auto_shift: config.auto_shift,
+            nkro_keys: [0; NKRO_USAGES / 8],
+            nkro_report_old: NkroReport::default(),
+            deferred_keys: 0,
+            deadline: None,
+            active_combos: Vec::new(),
+            tap_dance: None,
+            mouse_keys: 0,
+            mouse_buttons: 0,
+            consumer_usage: 0,
+            system_usage: 0,
+            mouse_move_time: Instant::MIN,
+            mouse_wheel_time: Instant::MIN,
+            rollover_local: false,
+            rollover_split: false,
+            rollover_keys: Vec::new(),
+            overflow_keys: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    pub fn host_mut(&mut self) -> &mut H {
+        &mut self.host
+    }
+
+    /// Time when `tick` has to be called, a tap-hold, combo, one-shot, caps word or leader key times out
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Process a key event, the events have to come in the order they happened
+    pub async fn event(&mut self, event: KeyEvent) {
+        // the events are logged as trace lines, see `trace`
+        #[cfg(feature = "defmt")]
+        info!("[trace] {}", Display2Format(&event));
+
+        // the timeouts before the event come first, e.g. a tap-hold key held past its tapping term
+        // and released before the tick ran is still a hold
+        self.tick(event.time()).await;
+
+        // a release carries the time the key was last seen pressed, the time never goes back
+        self.now = self.now.max(event.time());
+
+        match event {
+            KeyEvent::Pressed(position, time) => {
+                let key = Key {
+                    code: self.get_keycode(&position),
+                    position,
+                    state: KeyState::Pressed,
+                    time,
+                };
+
+                // the keys of the split half are placed after the local ones
+                let matrix_keys_half = if position.col as usize >= COLS {
+                    &mut self.matrix_keys_local[MATRIX_KEYS_BUFFER..]
+                } else {
+                    &mut self.matrix_keys_local[..MATRIX_KEYS_BUFFER]
+                };
+
+                // set the new key in an empty slot
+                if let Some(slot) = matrix_keys_half
+                    .iter_mut()
+                    .find(|key| key.position == KeyPos::default())
+                {
+                    *slot = key;
+                } else {
+                    // the press is lost, the rollover lasts until the key is released
+                    self.rollover_keys.push(position).ok();
+                }
+            }
+            KeyEvent::Released(position, _) => {
+                if let Some(index) = self.rollover_keys.iter().position(|pos| *pos == position) {
+                    self.rollover_keys.swap_remove(index);
+                } else if let Some(key) = self
+                    .matrix_keys_local
+                    .iter_mut()
+                    .find(|key| key.position == position && key.state != KeyState::Released)
+                {
+                    key.state = KeyState::Released;
+                }
+            }
+            KeyEvent::Rollover {
+                split: true,
+                active,
+                ..
+            } => self.rollover_split = active,
+            KeyEvent::Rollover { active, .. } => self.rollover_local = active,
+        }
+
+        self.process().await;
+    }
+
+    /// Release the keys of the split half, their releases are lost when the split link goes down
+    pub async fn release_split(&mut self, time: Instant) {
+        self.tick(time).await;
+        self.now = self.now.max(time);
+
+        for key in self.matrix_keys_local[MATRIX_KEYS_BUFFER..]
+            .iter_mut()
+            .filter(|key| key.position != KeyPos::default())
+        {
+            key.state = KeyState::Released;
+        }
+        self.rollover_split = false;
+        self.rollover_keys.retain(|pos| (pos.col as usize) < COLS);
+
+        self.process().await;
+    }
+
+    /// Process the timeouts up to the given time, each one at its deadline
+    pub async fn tick(&mut self, now: Instant) {
+        while let Some(deadline) = self.deadline
+            && deadline <= now
+        {
+            self.now = self.now.max(deadline);
+            self.process().await;
+        }
+    }
+
+    /// Provision the registered keys
+    async fn process(&mut self) {
+        let now = self.now;
+
+        // the helpers borrow the engine while the matrix keys are changed
+        let mut matrix_keys_local = core::mem::take(&mut self.matrix_keys_local);
+        let mut keys_to_remove: Vec<usize, { MATRIX_KEYS_COMB_BUFFER }> = Vec::new();
+
+        // provision combos
+        self.provision_combos(&mut matrix_keys_local).await;
+
+        #[cfg(feature = "defmt")]
+        info!("[engine] matrix_keys_local: {:#?}", matrix_keys_local);
+
+        // process the registered keys in the order they were pressed
+        let mut keys_ordered: Vec<usize, { MATRIX_KEYS_COMB_BUFFER }> = (0
+            ..MATRIX_KEYS_COMB_BUFFER)
+            .filter(|&index| matrix_keys_local[index].position != KeyPos::default())
+            .collect();
+        keys_ordered.sort_unstable_by_key(|&index| matrix_keys_local[index].time);
+
+        // keys pressed after an unresolved tap-hold key are held back
+        let mut blocked = false;
+
+        self.deadline = None;
+
+        // one-shot modifiers and layers time out
+        self.expire_oneshot(now);
+
+        // caps word is turned off after its timeout
+        self.expire_caps_word(now);
+
+        // the leader sequence ends after its timeout
+        self.expire_leader(now).await;
+
+        // the tap dance ends after its tapping term
+        self.tick_tap_dance(now).await;
+
+        for index in keys_ordered {
+            // the press of the key has not been sent yet
+            let mut press_pending = false;
+
+            let key = matrix_keys_local[index];
+
+            if blocked {
+                if key.state == KeyState::Pressed {
+                    self.deferred_keys |= 1 << index;
+                }
+                continue;
+            }
+
+            if self.deferred_keys & (1 << index) != 0 {
+                // a layer-tap may have changed the layer while the key was held back
+                self.deferred_keys &= !(1 << index);
+                matrix_keys_local[index].code = self.get_keycode(&key.position);
+                press_pending = true;
+            }
+
+            // tap-dance keys are resolved from their presses and releases
+            if let Some(tap_dance) = matrix_keys_local[index]
+                .code
+                .get_tap_dance(self.config.tap_dance_keys)
+            {
+                match key.state {
+                    KeyState::Pressed => {
+                        self.tap_dance_pressed(&key, tap_dance).await;
+                        matrix_keys_local[index].state = KeyState::Held;
+                    }
+                    KeyState::Held => {}
+                    KeyState::Released => {
+                        if press_pending {
+                            self.tap_dance_pressed(&key, tap_dance).await;
+                        }
+                        self.tap_dance_released(&key.position, now).await;
+                        keys_to_remove
+                            .push(index)
+                            .expect("[engine] keys_to_remove is full");
+                    }
+                }
+                continue;
+            }
+
+            // an other key press ends the tap dance
+            if key.state == KeyState::Pressed || press_pending {
+                self.finish_tap_dance().await;
+            }
+
+            // wait for the remaining keys of a combo
+            if key.state == KeyState::Pressed
+                && key.code != KC::NoOp
+                && self.combo_pending(&matrix_keys_local, index, now)
+            {
+                blocked = true;
+                self.deferred_keys |= 1 << index;
+                continue;
+            }
+
+            if let Some(tap_hold) = matrix_keys_local[index]
+                .code
+                .get_tap_hold(self.config.tap_hold_keys)
+            {
+                match self.resolve_tap_hold(&matrix_keys_local, index, tap_hold, now) {
+                    Some(code) => {
+                        matrix_keys_local[index].code = code;
+                        press_pending = true;
+
+                        // the later keys are looked up again, a layer-tap changes their layer
+                        for (slot, other) in matrix_keys_local.iter().enumerate() {
+                            if other.state == KeyState::Pressed
+                                && slot != index
+                                && other.time >= key.time
+                                && other.code != KC::NoOp
+                            {
+                                self.deferred_keys |= 1 << slot;
+                            }
+                        }
+                    }
+                    None => {
+                        blocked = true;
+                        continue;
+                    }
+                }
+            } else if (key.state == KeyState::Pressed || press_pending)
+                && self.auto_shift(&matrix_keys_local[index].code)
+            {
+                // a tap sends the key, holding it past the term sends it shifted
+                let kc = matrix_keys_local[index].code;
+                let auto_shift = TapHold {
+                    tap: kc,
+                    hold: LS(kc),
+                    flavor: HoldFlavor::TapPreferred,
+                    tapping_term: AUTO_SHIFT_TERM,
+                };
+
+                match self.resolve_tap_hold(&matrix_keys_local, index, &auto_shift, now) {
+                    Some(code) => {
+                        matrix_keys_local[index].code = code;
+                        press_pending = true;
+                    }
+                    None => {
+                        // the press is sent once the key is resolved
+                        self.deferred_keys |= 1 << index;
+                        blocked = true;
+                        continue;
+                    }
+                }
+            }
+
+            let key = matrix_keys_local[index];
+
+            match key.state {
+                KeyState::Pressed => {
+                    if key.code != KC::default() {
+                        // get the keycode
+                        self.press_key(&mut matrix_keys_local[index]).await;
+                    }
+
+                    // provision the press only once
+                    matrix_keys_local[index].state = KeyState::Held;
+                }
+                KeyState::Held => {}
+                KeyState::Released => {
+                    if key.code != KC::default() {
+                        // the press was held back (e.g. a combo key released before the combo
+                        // completed), it is sent as a plain tap
+                        if press_pending {
+                            self.press_key(&mut matrix_keys_local[index]).await;
+                            self.send_report().await;
+                        }
+
+                        // remove the kc from keyreport_local
+                        self.provision_released_keys(&matrix_keys_local[index].code, &key.position)
+                            .await;
+
+                        if press_pending {
+                            self.send_report().await;
+                        }
+
+                        // the modifiers suppressed by the key override are sent again
+                        if self.override_position == Some(key.position) {
+                            self.end_key_override();
+                        }
+                    }
+
+                    // evaluate enter_bootloader
+                    self.evaluate_enter_bootloader(&key);
+
+                    // remember the key to be removed
+                    keys_to_remove
+                        .push(index)
+                        .expect("[engine] keys_to_remove is full");
+                }
+            }
+        }
+
+        // remove the released keys
+        while let Some(index) = keys_to_remove.pop() {
+            #[cfg(feature = "defmt")]
+            info!(
+                "[engine] keys_to_remove key: {}",
+                matrix_keys_local[index].code.code()
+            );
+            matrix_keys_local[index] = Key::default();
+            self.deferred_keys &= !(1 << index);
+        }
+
+        // send report
+        self.send_report().await;
+
+        // move the mouse while the mouse keys are held
+        self.tick_mouse(now).await;
+
+        #[cfg(feature = "defmt")]
+        info!(
+            "[engine] keyreport_local.keycodes: {:?}",
+            self.keyreport_local.keycodes
+        );
+
+        self.matrix_keys_local = matrix_keys_local;
+    }
+
+    /// Evaluate if condition is met to enter bootloader
+    fn evaluate_enter_bootloader(&mut self, key: &Key) {
+        if self.now >= key.time + BOOTLOADER_HOLD
+            && (key.position == BOOTLOADER_KEY || key.code == KC::BTL)
+        {
+            self.host.enter_bootloader();
+        }
+    }
+
+    /// Get the keycode of a key position on the highest active layer,
+    /// transparent keys fall through to the active layers below
+    fn get_keycode(&self, key_pos: &KeyPos) -> KC {
+        let active_layers = self.active_layers();
+
+        (0..LAYERS)
+            .rev()
+            .filter(|layer| active_layers & (1 << layer) != 0)
+            .map(|layer| self.config.keymap[layer][key_pos.row as usize][key_pos.col as usize])
+            .find(|kc| *kc != KC::Trans)
+            .unwrap_or(KC::NoOp)
+    }
+
+    /// Get the highest active layer
+    fn highest_layer(&self) -> u8 {
+        let active_layers = self.active_layers();
+        (u32::BITS - 1 - active_layers.leading_zeros()) as u8
+    }
+
+    /// Get the bitmask of the active layers, with the default and the conditional layers
+    fn active_layers(&self) -> u32 {
+        self.layer_state | self.condition_layers | (1 << self.default_layer)
+    }
+
+    /// Turn on the conditional layers whose layers are active, after every layer change.
+    /// A conditional layer can be the condition of the rules after it
+    fn update_layer_conditions(&mut self) {
+        self.condition_layers = 0;
+
+        for condition in self.config.layer_conditions {
+            if (condition.then_layer as usize) < LAYERS
+                && self.active_layers() & condition.if_layers == condition.if_layers
+            {
+                self.condition_layers |= 1 << condition.then_layer;
+            }
+        }
+    }
+
+    /// Wake up the provision loop at the deadline, the earliest one wins
+    fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(self.deadline.map_or(deadline, |d| d.min(deadline)));
+    }
+
+    /// Turn on a layer
+    fn layer_on(&mut self, layer: u8) {
+        if (layer as usize) < LAYERS {
+            self.layer_state |= 1 << layer;
+            self.update_layer_conditions();
+        }
+    }
+
+    /// Turn off a layer
+    fn layer_off(&mut self, layer: u8) {
+        self.layer_state &= !(1 << layer);
+        self.update_layer_conditions();
+    }
+
+    /// Lock the held momentary layers, they stay on after their keys are released.
+    /// Without a held layer to lock, the layers it locked are unlocked
+    fn layer_lock(&mut self) {
+        let held_layers = self
+            .layer_keys
+            .iter()
+            .fold(0u32, |layers, (_, layer)| layers | 1 << layer);
+
+        let new_layers = held_layers & !self.locked_layers;
+        if new_layers != 0 {
+            self.locked_layers |= new_layers;
+            self.layer_lock_layers |= new_layers;
+            return;
+        }
+
+        // the layers locked by a double tap of their one-shot key stay locked
+        for layer in 0..LAYERS as u8 {
+            if self.layer_lock_layers & !held_layers & (1 << layer) != 0 {
+                self.layer_off(layer);
+            }
+        }
+        self.locked_layers &= !self.layer_lock_layers;
+        self.layer_lock_layers = 0;
+    }
+
+    /// Provision the press of a key, a key override replaces the key while its modifiers are active
+    async fn press_key(&mut self, key: &mut Key) {
+        // the repeat keys send the last key or its alternate, they are not remembered themselves
+        let repeated = matches!(KeyType::check_type(&key.code), KeyType::Repeat);
+        if repeated {
+            key.code = self.repeat_key(&key.code);
+        }
+
+        if matches!(KeyType::check_type(&key.code), KeyType::Key) {
+            // the keys after the leader key are not sent, they are matched against the sequences
+            if self.leader.is_some() {
+                self.leader_key(key.code).await;
+                key.code = KC::NoOp;
+                return;
+            }
+
+            // the modifiers stay suppressed until the replacement is released, so an other key
+            // press can't send them along with it
+            let modifier = self.held_mods | self.oneshot_mods | self.locked_mods;
+            let layer = self.highest_layer();
+
+            if let Some(key_override) = self
+                .config
+                .key_overrides
+                .iter()
+                .find(|key_override| key_override.matches(&key.code, modifier, layer))
+            {
+                #[cfg(feature = "defmt")]
+                info!(
+                    "[key_override] {} replaced by {}",
+                    key.code, key_override.replacement
+                );
+
+                key.code = key_override.replacement;
+                self.override_position = Some(key.position);
+                self.suppressed_mods = key_override.modifier();
+                self.update_modifier();
+            }
+
+            if !repeated {
+                self.last_key = Some(key.code.with_mods(modifier & !self.suppressed_mods));
+            }
+        }
+
+        self.provision_pressed_keys(&key.code, &key.position).await;
+    }
+
+    /// Get the key sent by a repeat key, no-op without a last key or alternate
+    fn repeat_key(&self, kc: &KC) -> KC {
+        let Some(last_key) = self.last_key else {
+            return KC::NoOp;
+        };
+
+        if *kc == KC::AltRepeat {
+            alt_repeat(&last_key, self.config.alt_repeat_keys).unwrap_or(KC::NoOp)
+        } else {
+            last_key
+        }
+    }
+
+    /// End the key override, its modifiers are no longer suppressed
+    fn end_key_override(&mut self) {
+        if self.override_position.take().is_some() {
+            self.suppressed_mods = 0;
+            self.update_modifier();
+        }
+    }
+
+    async fn provision_pressed_keys(&mut self, kc: &KC, key_pos: &KeyPos) {
+        // get the key type
+        match KeyType::check_type(kc) {
+            KeyType::Macro => {
+                if let Some(steps) = kc.get_macro(self.config.macros) {
+                    self.play_macro(steps).await;
+                }
+            }
+            KeyType::Layer => {
+                // momentary layer, held by the key until it is released
+                self.layer_keys
+                    .push((*key_pos, kc.get_layer()))
+                    .expect("[engine] layer_keys is full");
+                self.layer_on(kc.get_layer());
+            }
+            KeyType::LayerLock => self.layer_lock(),
+            KeyType::LayerToggle => {
+                let layer = kc.get_layer();
+                if self.layer_state & (1 << layer) != 0 {
+                    self.layer_off(layer);
+                } else {
+                    self.layer_on(layer);
+                }
+            }
+            KeyType::LayerTo => {
+                // the held layer keys no longer hold a layer
+                self.layer_state = 0;
+                self.oneshot_layer = None;
+                self.locked_layers = 0;
+                self.layer_lock_layers = 0;
+                self.layer_keys.clear();
+                self.layer_on(kc.get_layer());
+            }
+            KeyType::LayerDefault if (kc.get_layer() as usize) < LAYERS => {
+                self.default_layer = kc.get_layer();
+                self.update_layer_conditions();
+            }
+            KeyType::LayerOneShot => {
+                let layer = kc.get_layer();
+                if self.locked_layers & (1 << layer) != 0 {
+                    // unlock the layer
+                    self.locked_layers &= !(1 << layer);
+                    self.layer_lock_layers &= !(1 << layer);
+                    self.layer_off(layer);
+                } else if self.oneshot_layer == Some(layer) {
+                    // double tap locks the layer
+                    self.oneshot_layer = None;
+                    self.locked_layers |= 1 << layer;
+                } else {
+                    self.layer_on(layer);
+                    self.oneshot_layer = Some(layer);
+                    self.arm_oneshot();
+                }
+            }
+            KeyType::Modifier => {
+                self.held_mods |= kc.get_modifier();
+                self.update_modifier();
+            }
+            KeyType::ModifierOneShot => {
+                let modifier = kc.get_modifier();
+                if self.locked_mods & modifier != 0 {
+                    // unlock the modifier
+                    self.locked_mods &= !modifier;
+                } else if self.oneshot_mods & modifier != 0 {
+                    // double tap locks the modifier
+                    self.oneshot_mods &= !modifier;
+                    self.locked_mods |= modifier;
+                } else {
+                    self.oneshot_mods |= modifier;
+                    self.arm_oneshot();
+                }
+                self.update_modifier();
+            }
+            KeyType::Mouse => {
+                self.mouse_keys |= mouse_bit(kc);
+
+                if kc.get_mouse_button() != 0 {
+                    self.mouse_buttons |= kc.get_mouse_button();
+                    self.send_mouse_report(0, 0, 0, 0).await;
+                }
+            }
+            KeyType::Consumer => {
+                self.consumer_usage = kc.get_consumer_usage();
+                self.host
+                    .send(HidReport::Consumer(MediaKeyboardReport {
+                        usage_id: self.consumer_usage,
+                    }))
+                    .await;
+            }
+            KeyType::SystemControl => {
+                self.system_usage = kc.get_system_usage();
+                self.host
+                    .send(HidReport::System(SystemControlReport {
+                        usage_id: self.system_usage,
+                    }))
+                    .await;
+            }
+            KeyType::CapsWord => {
+                if self.caps_word {
+                    self.caps_word_off();
+                } else {
+                    self.caps_word = true;
+                    self.caps_word_time = self.now;
+                    self.set_deadline(self.caps_word_time + CAPS_WORD_TIMEOUT);
+                }
+            }
+            KeyType::AutoShift => self.auto_shift = !self.auto_shift,
+            KeyType::Leader => {
+                self.leader = Some(Vec::new());
+                self.leader_time = self.now;
+                self.set_deadline(self.leader_time + LEADER_TIMEOUT);
+            }
+            KeyType::Key => {
+                // caps word shifts the letters, a non-word key ends it
+                if self.caps_word {
+                    self.caps_word_key(kc);
+                }
+
+                self.press_key_mods(kc);
+                self.add_key(kc);
+
+                // the one-shot modifiers are sent with this key press only
+                if self.oneshot_mods != 0 {
+                    self.send_report().await;
+                    self.oneshot_mods = 0;
+                    self.update_modifier();
+                }
+
+                // the one-shot layer was used by this key
+                if let Some(layer) = self.oneshot_layer.take() {
+                    self.layer_off(layer);
+                }
+            }
+            // the report mode is switched when the key is released
+            KeyType::ReportMode => {}
+            // a layer beyond the keymap is not made the default layer
+            KeyType::LayerDefault => {}
+            // the repeat keys are replaced by the repeated key in `press_key`
+            KeyType::Repeat => {}
+            // the combo, tap-hold and tap-dance keys are resolved in `process` before their
+            // output is pressed
+            KeyType::Combo | KeyType::TapHold | KeyType::TapDance => {}
+            KeyType::NoOp => {}
+        }
+    }
+
+    async fn provision_released_keys(&mut self, kc: &KC, key_pos: &KeyPos) {
+        // get the key type
+        match KeyType::check_type(kc) {
+            KeyType::Layer => {
+                let layer = kc.get_layer();
+                let held_keys = self.layer_keys.len();
+                self.layer_keys
+                    .retain(|(pos, held)| !(pos == key_pos && *held == layer));
+
+                // turn off the momentary layer, unless an other key holds it or it is locked, a
+                // to-layer key pressed meanwhile released it from the key
+                if self.layer_keys.len() < held_keys
+                    && !self.layer_keys.iter().any(|(_, held)| *held == layer)
+                    && self.locked_layers & (1 << layer) == 0
+                {
+                    self.layer_off(layer);
+                }
+            }
+            KeyType::Modifier => {
+                // remove the modifier
+                self.held_mods &= !kc.get_modifier();
+                self.update_modifier();
+            }
+            KeyType::Mouse => {
+                self.mouse_keys &= !mouse_bit(kc);
+
+                if kc.get_mouse_button() != 0 {
+                    self.mouse_buttons &= !kc.get_mouse_button();
+                    self.send_mouse_report(0, 0, 0, 0).await;
+                }
+            }
+            // an other media key may have been pressed since
+            KeyType::Consumer if self.consumer_usage == kc.get_consumer_usage() => {
+                self.consumer_usage = 0;
+                self.host
+                    .send(HidReport::Consumer(MediaKeyboardReport { usage_id: 0 }))
+                    .await;
+            }
+            KeyType::ReportMode => {
+                let nkro = match *kc {
+                    KC::NkOn => true,
+                    KC::NkOff => false,
+                    _ => !self.nkro,
+                };
+                self.set_nkro(nkro).await;
+            }
+            // an other system key may have been pressed since
+            KeyType::SystemControl if self.system_usage == kc.get_system_usage() => {
+                self.system_usage = 0;
+                self.host
+                    .send(HidReport::System(SystemControlReport { usage_id: 0 }))
+                    .await;
+            }
+            KeyType::Key => {
+                self.remove_key(kc);
+                self.release_key_mods(kc);
+            }
+            _ => {}
+        }
+    }
+
+    /// Set the report modifier from the held, one-shot and locked modifiers
+    fn update_modifier(&mut self) {
+        self.keyreport_local.modifier = (self.held_mods
+            | self.oneshot_mods
+            | self.locked_mods
+            | self.caps_word_mods
+            | self.key_mods)
+            & !self.suppressed_mods;
+    }
+
+    /// Start the timeout of the one-shot modifiers and layer
+    fn arm_oneshot(&mut self) {
+        self.oneshot_time = self.now;
+        self.set_deadline(self.oneshot_time + ONESHOT_TIMEOUT);
+    }
+
+    /// Cancel the one-shot modifiers and layer when no key was pressed within the timeout
+    fn expire_oneshot(&mut self, now: Instant) {
+        if self.oneshot_mods == 0 && self.oneshot_layer.is_none() {
+            return;
+        }
+
+        let timeout = self.oneshot_time + ONESHOT_TIMEOUT;
+        if now < timeout {
+            self.set_deadline(timeout);
+            return;
+        }
+
+        self.oneshot_mods = 0;
+        self.update_modifier();
+
+        if let Some(layer) = self.oneshot_layer.take() {
+            self.layer_off(layer);
+        }
+    }
+
+    /// Shift a key pressed during caps word, a non-word key turns caps word off
+    fn caps_word_key(&mut self, kc: &KC) {
+        match kc.caps_word_shift() {
+            Some(shift) => {
+                self.caps_word_mods = if shift { KC::LShift.get_modifier() } else { 0 };
+                self.caps_word_time = self.now;
+                self.update_modifier();
+            }
+            None => self.caps_word_off(),
+        }
+    }
+
+    /// Turn caps word off when no key was pressed within the timeout
+    fn expire_caps_word(&mut self, now: Instant) {
+        if !self.caps_word {
+            return;
+        }
+
+        let timeout = self.caps_word_time + CAPS_WORD_TIMEOUT;
+        if now < timeout {
+            self.set_deadline(timeout);
+            return;
+        }
+
+        self.caps_word_off();
+    }
+
+    /// Turn caps word off and release its shift
+    fn caps_word_off(&mut self) {
+        self.caps_word = false;
+        self.caps_word_mods = 0;
+        self.update_modifier();
+    }
+
+    /// Check if a key is shifted when held past the `AUTO_SHIFT_TERM`
+    fn auto_shift(&self, kc: &KC) -> bool {
+        // the letters are followed by the digits in the usage ids
+        let letter_or_digit = (KC::Aa.code()..=KC::K0.code()).contains(&kc.code());
+
+        self.auto_shift
+            && self.held_mods == 0
+            && kc.mods() == 0
+            && letter_or_digit
+            && !self.config.auto_shift_excluded.contains(kc)
+    }
+
+    /// Add a key to the leader sequence, the output is sent once no longer sequence can match
+    async fn leader_key(&mut self, kc: KC) {
+        let Some(keys) = self.leader.as_mut() else {
+            return;
+        };
+
+        self.leader_time = self.now;
+
+        // more keys than any sequence has
+        if keys.push(kc).is_err() {
+            self.leader = None;
+            return;
+        }
+
+        let longer =
+            self.config.leader_sequences.iter().any(|sequence| {
+                sequence.keys.len() > keys.len() && sequence.keys.starts_with(keys)
+            });
+
+        if longer {
+            self.set_deadline(self.leader_time + LEADER_TIMEOUT);
+        } else {
+            self.finish_leader().await;
+        }
+    }
+
+    /// End the leader sequence when no key was pressed within the timeout
+    async fn expire_leader(&mut self, now: Instant) {
+        if self.leader.is_none() {
+            return;
+        }
+
+        let timeout = self.leader_time + LEADER_TIMEOUT;
+        if now < timeout {
+            self.set_deadline(timeout);
+            return;
+        }
+
+        self.finish_leader().await;
+    }
+
+    /// End the leader sequence and tap the output of the matching sequence
+    async fn finish_leader(&mut self) {
+        let Some(keys) = self.leader.take() else {
+            return;
+        };
+
+        let Some(sequence) = self
+            .config
+            .leader_sequences
+            .iter()
+            .find(|sequence| sequence.keys == keys.as_slice())
+        else {
+            return;
+        };
+
+        #[cfg(feature = "defmt")]
+        info!("[leader] sequence: {:?}", sequence.keys);
+
+        // the sequence keys are not held by a physical key
+        for kc in sequence.action.keys() {
+            self.provision_pressed_keys(kc, &KeyPos::default()).await;
+        }
+        self.send_report().await;
+        for kc in sequence.action.keys().iter().rev() {
+            self.provision_released_keys(kc, &KeyPos::default()).await;
+        }
+    }
+
+    /// Send the modifiers of a modified key with it, they are released by an other key press
+    fn press_key_mods(&mut self, kc: &KC) {
+        if self.key_mods != kc.mods() {
+            self.key_mods = kc.mods();
+            self.update_modifier();
+        }
+    }
+
+    /// Release the modifiers of a modified key, unless an other key was pressed since
+    fn release_key_mods(&mut self, kc: &KC) {
+        if kc.mods() != 0 && self.key_mods == kc.mods() {
+            self.key_mods = 0;
+            self.update_modifier();
+        }
+    }
+
+    /// Add a key to the keyreport_local or the nkro bitmap
+    fn add_key(&mut self, kc: &KC) {
+        let usage = kc.code() as usize;
+        if self.nkro && usage < NKRO_USAGES {
+            self.nkro_keys[usage / 8] |= 1 << (usage % 8);
+            return;
+        }
+
+        // check if the key count is less than 6
+        if !self.keyreport_local.keycodes.contains(&(kc.code() as u8)) {
+            // find the first key slot in the array that is free
+            if let Some(index) = self
+                .keyreport_local
+                .keycodes
+                .iter()
+                .position(|&value| value == 0)
+            {
+                // add the new key to that position
+                self.keyreport_local.keycodes[index] = kc.code() as u8
+            } else if !self.overflow_keys.contains(&(kc.code() as u8)) {
+                // all 6 keycodes are taken, report an error rollover until one is free
+                self.overflow_keys
+                    .push(kc.code() as u8)
+                    .expect("[engine] overflow_keys is full");
+            }
+        }
+    }
+
+    /// Remove a key from the keyreport_local or the nkro bitmap
+    fn remove_key(&mut self, kc: &KC) {
+        let usage = kc.code() as usize;
+        if self.nkro && usage < NKRO_USAGES {
+            self.nkro_keys[usage / 8] &= !(1 << (usage % 8));
+            return;
+        }
+
+        if let Some(index) = self
+            .overflow_keys
+            .iter()
+            .position(|&value| value == kc.code() as u8)
+        {
+            self.overflow_keys.remove(index);
+        }
+        // find the key index of the released key
+        else if let Some(index) = self
+            .keyreport_local
+            .keycodes
+            .iter()
+            .position(|&value| value == kc.code() as u8)
+        {
+            // remove the key from the keyreport_local, an overflowed key takes its place
+            self.keyreport_local.keycodes[index] = if self.overflow_keys.is_empty() {
+                0
+            } else {
+                self.overflow_keys.remove(0)
+            };
+        }
+    }
+
+    /// Press a key or modifier of a macro step
+    fn macro_press(&mut self, kc: &KC) {
+        match KeyType::check_type(kc) {
+            KeyType::Modifier => {
+                self.held_mods |= kc.get_modifier();
+                self.update_modifier();
+            }
+            KeyType::Key => {
+                self.press_key_mods(kc);
+                self.add_key(kc);
+            }
+            _ => {}
+        }
+    }
+
+    /// Release a key or modifier of a macro step
+    fn macro_release(&mut self, kc: &KC) {
+        match KeyType::check_type(kc) {
+            KeyType::Modifier => {
+                self.held_mods &= !kc.get_modifier();
+                self.update_modifier();
+            }
+            KeyType::Key => {
+                self.remove_key(kc);
+                self.release_key_mods(kc);
+            }
+            _ => {}
+        }
+    }
+
+    /// Play the steps of a macro, every intermediate report is sent
+    async fn play_macro(&mut self, steps: &[MacroStep]) {
+        for step in steps {
+            match *step {
+                MacroStep::Tap(kc) => {
+                    self.macro_press(&kc);
+                    self.send_report().await;
+                    self.macro_release(&kc);
+                    self.send_report().await;
+                }
+                MacroStep::Press(kc) => {
+                    self.macro_press(&kc);
+                    self.send_report().await;
+                }
+                MacroStep::Release(kc) => {
+                    self.macro_release(&kc);
+                    self.send_report().await;
+                }
+                MacroStep::Delay(ms) => self.host.delay(Duration::from_millis(ms)).await,
+                MacroStep::Type(text) => {
+                    for (kc, shift) in text.bytes().filter_map(ascii_to_kc) {
+                        let modifier = self.keyreport_local.modifier;
+
+                        // the held shift keys (left and right) must not change the typed characters
+                        self.keyreport_local.modifier &=
+                            !(KC::LShift.get_modifier() | KC::RShift.get_modifier());
+                        if shift {
+                            self.keyreport_local.modifier |= KC::LShift.get_modifier();
+                        }
+
+                        self.add_key(&kc);
+                        self.send_report().await;
+                        self.remove_key(&kc);
+                        self.keyreport_local.modifier = modifier;
+                        self.send_report().await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Switch between nkro and 6kro, the pressed keys are released
+    async fn set_nkro(&mut self, nkro: bool) {
+        if nkro == self.nkro {
+            return;
+        }
+
+        self.nkro_keys = [0; NKRO_USAGES / 8];
+        self.keyreport_local.keycodes = [0; 6];
+        self.overflow_keys.clear();
+        self.send_report().await;
+
+        // release the keys and modifiers of the nkro report before leaving nkro mode
+        if self.nkro {
+            self.nkro_report_old = NkroReport::default();
+            self.host.send(HidReport::Nkro(self.nkro_report_old)).await;
+        }
+
+        self.nkro = nkro;
+    }
+
+    /// Get the 6kro report, in nkro mode the modifiers are sent in the nkro report
+    fn boot_report(&self) -> KeyboardReport {
+        let mut report = self.keyreport_local;
+        if self.nkro {
+            report.modifier = 0;
+        }
+
+        // too many keys are pressed to report them all
+        if self.rollover_local
+            || self.rollover_split
+            || !self.rollover_keys.is_empty()
+            || !self.overflow_keys.is_empty()
+        {
+            report.keycodes = [KC::ERO.code() as u8; 6];
+        }
+
+        report
+    }
+
+    /// Get the nkro report
+    fn nkro_report(&self) -> NkroReport {
+        NkroReport {
+            modifier: self.keyreport_local.modifier,
+            keys: self.nkro_keys,
+        }
+    }
+
+    /// Check if the report differs from the last one sent
+    fn boot_report_changed(&self, report: &KeyboardReport) -> bool {
+        report.modifier != self.keyreport_local_old.modifier
+            || report.keycodes != self.keyreport_local_old.keycodes
+    }
+
+    /// Send the changed key reports, waits while the report queue is full
+    async fn send_report(&mut self) {
+        if self.nkro {
+            let report = self.nkro_report();
+            if report != self.nkro_report_old {
+                self.host.send(HidReport::Nkro(report)).await;
+                self.nkro_report_old = report;
+            }
+        }
+
+        let report = self.boot_report();
+        if self.boot_report_changed(&report) {
+            self.host.send(HidReport::Keyboard(report)).await;
+            self.keyreport_local_old = report;
+        }
+    }
+
+    /// Decide if a pending tap-hold key is a tap or a hold
+    fn resolve_tap_hold(
+        &mut self,
+        matrix_keys_local: &[Key; MATRIX_KEYS_COMB_BUFFER],
+        index: usize,
+        tap_hold: &TapHold,
+        now: Instant,
+    ) -> Option<KC> {
+        let key = matrix_keys_local[index];
+
+        // keys pressed after the tap-hold key
+        let mut later_keys = matrix_keys_local.iter().filter(|other| {
+            other.position != KeyPos::default()
+                && other.position != key.position
+                && other.time >= key.time
+        });
+
+        let resolved = if key.state == KeyState::Released {
+            // released within the tapping term
+            Some(tap_hold.tap)
+        } else if now >= key.time + tap_hold.tapping_term {
+            Some(tap_hold.hold)
+        } else {
+            let interrupted = match tap_hold.flavor {
+                HoldFlavor::HoldPreferred => later_keys.next().is_some(),
+                HoldFlavor::Balanced => later_keys.any(|other| other.state == KeyState::Released),
+                HoldFlavor::TapPreferred => false,
+            };
+            interrupted.then_some(tap_hold.hold)
+        };
+
+        if resolved.is_none() {
+            self.set_deadline(key.time + tap_hold.tapping_term);
+        }
+
+        #[cfg(feature = "defmt")]
+        if let Some(code) = resolved {
+            info!("[tap_hold] resolved to: {}", code);
+        }
+
+        resolved
+    }
+
+    /// Provision combo keys
+    async fn provision_combos(&mut self, matrix_keys_local: &mut [Key; MATRIX_KEYS_COMB_BUFFER]) {
+        // release the output of the active combos
+        let mut index = 0;
+        while index < self.active_combos.len() {
+            let active = &mut self.active_combos[index];
+            let members = active.members;
+
+            for (slot, key) in matrix_keys_local.iter().enumerate() {
+                if key.state == KeyState::Released {
+                    active.members &= !(1 << slot);
+                }
+            }
+
+            let released = match active.combo.release {
+                ComboRelease::AnyReleased => active.members != members,
+                ComboRelease::AllReleased => active.members == 0,
+            };
+
+            if released {
+                let combo = self.active_combos.swap_remove(index).combo;
+                for kc in combo.output() {
+                    self.provision_released_keys(kc, &combo.keys[0]).await;
+                }
+            } else {
+                index += 1;
+            }
+        }
+
+        let layer = self.highest_layer();
+
+        let combos = self.config.combos;
+        for combo in combos
+            .iter()
+            .filter(|combo| combo.layers & (1 << layer) != 0)
+        {
+            let mut members = 0u32;
+            let mut first = Instant::MAX;
+            let mut last = Instant::MIN;
+
+            // all combo keys have to be pressed, but not provisioned yet
+            for key_pos in combo.keys {
+                match matrix_keys_local.iter().position(|key| {
+                    key.position == *key_pos
+                        && key.state == KeyState::Pressed
+                        && key.code != KC::NoOp
+                }) {
+                    Some(slot) => {
+                        members |= 1 << slot;
+                        first = first.min(matrix_keys_local[slot].time);
+                        last = last.max(matrix_keys_local[slot].time);
+                    }
+                    None => {
+                        members = 0;
+                        break;
+                    }
+                }
+            }
+
+            if members == 0 || last > first + combo.timeout {
+                continue;
+            }
+
+            #[cfg(feature = "defmt")]
+            info!("[combo] combo activated: {:?}", combo.keys);
+
+            // the combo keys are consumed by the combo
+            for (slot, key) in matrix_keys_local.iter_mut().enumerate() {
+                if members & (1 << slot) != 0 {
+                    key.code = KC::NoOp;
+                    self.deferred_keys &= !(1 << slot);
+                }
+            }
+
+            // the output is held by the first key of the combo
+            for kc in combo.output() {
+                self.provision_pressed_keys(kc, &combo.keys[0]).await;
+            }
+
+            self.active_combos
+                .push(ActiveCombo { combo, members })
+                .ok()
+                .expect("[engine] active_combos is full");
+        }
+    }
+
+    /// Check if a pressed key may still become part of a combo
+    fn combo_pending(
+        &mut self,
+        matrix_keys_local: &[Key; MATRIX_KEYS_COMB_BUFFER],
+        index: usize,
+        now: Instant,
+    ) -> bool {
+        let key = matrix_keys_local[index];
+        let layer = self.highest_layer();
+        let mut pending = false;
+
+        let combos = self.config.combos;
+        for combo in combos
+            .iter()
+            .filter(|combo| combo.layers & (1 << layer) != 0 && combo.keys.contains(&key.position))
+        {
+            let mut first = key.time;
+            let mut possible = true;
+
+            for key_pos in combo.keys {
+                match matrix_keys_local
+                    .iter()
+                    .find(|other| other.position == *key_pos && other.position != KeyPos::default())
+                {
+                    Some(other) if other.state == KeyState::Pressed && other.code != KC::NoOp => {
+                        first = first.min(other.time);
+                    }
+                    // a combo key is already provisioned or released
+                    Some(_) => possible = false,
+                    None => {}
+                }
+            }
+
+            let deadline = first + combo.timeout;
+            if possible && now < deadline {
+                self.set_deadline(deadline);
+                pending = true;
+            }
+        }
+
+        pending
+    }
+
+    /// Count the press of a tap-dance key
+    async fn tap_dance_pressed(&mut self, key: &Key, tap_dance: &'static TapDance) {
+        if let Some(active) = self.tap_dance.as_mut()
+            && active.position == key.position
+            && active.output.is_none()
+        {
+            active.taps = active.taps.saturating_add(1);
+            active.pressed = true;
+            active.time = key.time;
+        } else {
+            // an other tap-dance key ends the running dance
+            self.finish_tap_dance().await;
+
+            self.tap_dance = Some(ActiveTapDance {
+                tap_dance,
+                position: key.position,
+                taps: 1,
+                pressed: true,
+                time: key.time,
+                output: None,
+            });
+        }
+
+        self.set_deadline(key.time + tap_dance.tapping_term);
+    }
+
+    /// Handle the release of a tap-dance key
+    async fn tap_dance_released(&mut self, key_pos: &KeyPos, now: Instant) {
+        let Some(active) = self.tap_dance.as_mut() else {
+            return;
+        };
+
+        if active.position != *key_pos {
+            return;
+        }
+
+        if let Some(output) = active.output {
+            // the dance was resolved while the key was held
+            self.tap_dance = None;
+            self.provision_released_keys(&output, key_pos).await;
+        } else if active.taps >= 3 {
+            // no more outputs to wait for
+            active.pressed = false;
+            self.finish_tap_dance().await;
+        } else {
+            let tapping_term = active.tap_dance.tapping_term;
+            active.pressed = false;
+            active.time = now;
+            self.set_deadline(now + tapping_term);
+        }
+    }
+
+    /// Resolve the running tap dance with the taps counted so far
+    async fn finish_tap_dance(&mut self) {
+        let Some(active) = self.tap_dance.as_mut() else {
+            return;
+        };
+
+        if active.output.is_some() {
+            return;
+        }
+
+        let output = active.tap_dance.output(active.taps, false);
+        let key_pos = active.position;
+
+        if active.pressed {
+            // keep the output pressed until the key is released
+            active.output = Some(output);
+            self.provision_pressed_keys(&output, &key_pos).await;
+        } else {
+            self.tap_dance = None;
+            self.provision_pressed_keys(&output, &key_pos).await;
+            self.send_report().await;
+            self.provision_released_keys(&output, &key_pos).await;
+        }
+    }
+
+    /// Resolve the running tap dance when its tapping term expired
+    async fn tick_tap_dance(&mut self, now: Instant) {
+        let Some(active) = self.tap_dance.as_mut() else {
+            return;
+        };
+
+        if active.output.is_some() {
+            return;
+        }
+
+        let deadline = active.time + active.tap_dance.tapping_term;
+        if now < deadline {
+            self.set_deadline(deadline);
+        } else if active.pressed {
+            // held past the tapping term
+            let output = active.tap_dance.output(active.taps, true);
+            let key_pos = active.position;
+            active.output = Some(output);
+            self.provision_pressed_keys(&output, &key_pos).await;
+        } else {
+            self.finish_tap_dance().await;
+        }
+    }
+
+    /// Send a mouse report with the pressed buttons and the given moves
+    async fn send_mouse_report(&mut self, x: i8, y: i8, wheel: i8, pan: i8) {
+        self.host
+            .send(HidReport::Mouse(MouseReport {
+                buttons: self.mouse_buttons,
+                x,
+                y,
+                wheel,
+                pan,
+            }))
+            .await;
+    }
+
+    /// Get the direction of a mouse axis from the pressed mouse keys
+    fn mouse_axis(&self, negative: KC, positive: KC) -> i8 {
+        let pressed = |kc: KC| (self.mouse_keys & mouse_bit(&kc) != 0) as i8;
+        pressed(positive) - pressed(negative)
+    }
+
+    /// Move the cursor and the wheel while the mouse keys are held
+    async fn tick_mouse(&mut self, now: Instant) {
+        // slow, normal or fast speed
+        let speed = if self.mouse_keys & mouse_bit(&KC::MoCF) != 0 {
+            2
+        } else if self.mouse_keys & mouse_bit(&KC::MoCS) != 0 {
+            0
+        } else {
+            1
+        };
+
+        let (mut x, mut y, mut wheel, mut pan) = (0, 0, 0, 0);
+
+        let move_x = self.mouse_axis(KC::MoGL, KC::MoGR);
+        let move_y = self.mouse_axis(KC::MoGU, KC::MoGD);
+        if move_x != 0 || move_y != 0 {
+            if now >= self.mouse_move_time {
+                x = move_x * MOUSE_MOVE_STEPS[speed];
+                y = move_y * MOUSE_MOVE_STEPS[speed];
+                self.mouse_move_time = now + MOUSE_MOVE_INTERVAL;
+            }
+            self.set_deadline(self.mouse_move_time);
+        }
+
+        let move_wheel = self.mouse_axis(KC::MoSD, KC::MoSU);
+        let move_pan = self.mouse_axis(KC::MoSL, KC::MoSR);
+        if move_wheel != 0 || move_pan != 0 {
+            if now >= self.mouse_wheel_time {
+                wheel = move_wheel * MOUSE_WHEEL_STEPS[speed];
+                pan = move_pan * MOUSE_WHEEL_STEPS[speed];
+                self.mouse_wheel_time = now + MOUSE_WHEEL_INTERVAL;
+            }
+            self.set_deadline(self.mouse_wheel_time);
+        }
+
+        if x != 0 || y != 0 || wheel != 0 || pan != 0 {
+            self.send_mouse_report(x, y, wheel, pan).await;
+        }
+    }
+}
+
+/// Get the bit of a mouse key in `Engine::mouse_keys`
+fn mouse_bit(kc: &KC) -> u16 {
+    1 << (kc.code() - KC::MoGL.code())
+}
+
+/// Get the alternate of a key from the `ALT_REPEAT_KEYS` pairs,
+/// a key with other modifiers keeps them (e.g. ctrl + left => ctrl + right)
+fn alt_repeat(kc: &KC, alt_repeat_keys: &[(KC, KC)]) -> Option<KC> {
+    let alternate = |kc: KC| {
+        alt_repeat_keys.iter().find_map(|&(first, second)| {
+            if kc == first {
+                Some(second)
+            } else if kc == second {
+                Some(first)
+            } else {
+                None
+            }
+        })
+    };
+
+    alternate(*kc).or_else(|| alternate(kc.without_mods()).map(|alt| alt.with_mods(kc.mods())))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        combo::ALL_LAYERS,
+        config::TAPPING_TERM,
+        fixture::{combo_engine, engine, keyboard_reports, keymap, keys, press, release, tap},
+        key_override::{EITHER_SHIFT, KeyOverride},
+    };
+    use embassy_futures::block_on;
+
+    #[test]
+    fn fast_taps_are_reported_in_order() {
+        let mut engine = engine(keymap(&[(1, 6, KC::Hh), (1, 7, KC::Tt)]));
+
+        press(&mut engine, 1, 6, 1000);
+        release(&mut engine, 1, 6, 1005);
+        press(&mut engine, 1, 7, 1006);
+        release(&mut engine, 1, 7, 1010);
+
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Hh)),
+                (0, [0; 6]),
+                (0, keys(KC::Tt)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn momentary_layer() {
+        let mut keymap = keymap(&[(3, 7, KC::L1), (1, 6, KC::Hh)]);
+        keymap[1][1][6] = KC::LeftArr;
+        let mut engine = engine(keymap);
+
+        press(&mut engine, 3, 7, 1000);
+        press(&mut engine, 1, 6, 1010);
+        release(&mut engine, 1, 6, 1020);
+        release(&mut engine, 3, 7, 1030);
+        press(&mut engine, 1, 6, 1040);
+
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::LeftArr)), (0, [0; 6]), (0, keys(KC::Hh))]
+        );
+    }
+
+    #[test]
+    fn tap_hold_tap_and_hold() {
+        let mut engine = engine(keymap(&[(1, 0, KC::TH1)]));
+        let ctrl = KC::LCtrl.get_modifier();
+
+        tap(&mut engine, 1, 0, 1000);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::Aa)), (0, [0; 6])]
+        );
+
+        press(&mut engine, 1, 0, 2000);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        block_on(engine.tick(Instant::from_millis(2000) + TAPPING_TERM));
+        release(&mut engine, 1, 0, 2500);
+        assert_eq!(keyboard_reports(&mut engine), [(ctrl, [0; 6]), (0, [0; 6])]);
+    }
+
+    #[test]
+    fn balanced_tap_hold_is_a_hold_when_an_other_key_is_tapped() {
+        let keymap = keymap(&[(1, 0, KC::TH1), (1, 1, KC::Bb)]);
+        let mut engine = engine(keymap);
+        let ctrl = KC::LCtrl.get_modifier();
+
+        // an other key tapped while the key is held
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1050);
+        release(&mut engine, 1, 0, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(ctrl, keys(KC::Bb)), (ctrl, [0; 6]), (0, [0; 6])]
+        );
+
+        // a roll, the tap-hold key is released first
+        press(&mut engine, 1, 0, 2000);
+        press(&mut engine, 1, 1, 2050);
+        release(&mut engine, 1, 0, 2080);
+        release(&mut engine, 1, 1, 2100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Aa)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn mod_tap_double_tapped_while_held_back() {
+        let keymap = keymap(&[(1, 0, KC::TH3), (1, 1, KC::TH1)]);
+        let mut engine = engine(keymap);
+
+        // both taps wait for the tap-preferred key, they are two presses of the same key
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1020);
+        tap(&mut engine, 1, 1, 1040);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        release(&mut engine, 1, 0, 1060);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Ee)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn key_tapped_twice_while_held_back() {
+        let keymap = keymap(&[(1, 0, KC::TH3), (1, 1, KC::Bb)]);
+        let mut engine = engine(keymap);
+
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1020);
+        tap(&mut engine, 1, 1, 1040);
+        release(&mut engine, 1, 0, 1060);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Ee)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn layer_tap_is_a_hold_when_an_other_key_is_pressed() {
+        let mut keymap = keymap(&[(3, 3, KC::TH2), (1, 1, KC::Bb)]);
+        keymap[1][1][1] = KC::LeftArr;
+        let mut engine = engine(keymap);
+
+        press(&mut engine, 3, 3, 1000);
+        press(&mut engine, 1, 1, 1050);
+        release(&mut engine, 1, 1, 1100);
+        release(&mut engine, 3, 3, 1150);
+        tap(&mut engine, 3, 3, 2000);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::Space)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn toggle_layer() {
+        let mut keymap = keymap(&[(1, 0, KC::TG1), (1, 1, KC::Bb)]);
+        keymap[1][1][0] = KC::TG1;
+        keymap[1][1][1] = KC::LeftArr;
+        let mut engine = engine(keymap);
+
+        tap(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        tap(&mut engine, 1, 0, 1200);
+        tap(&mut engine, 1, 1, 1300);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn oneshot_modifier_applies_to_the_next_key() {
+        let keymap = keymap(&[(1, 0, KC::OSMLShift), (1, 1, KC::Aa)]);
+        let mut engine = engine(keymap);
+        let shift = KC::LShift.get_modifier();
+
+        // the modifier is released once the key press is sent
+        tap(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        tap(&mut engine, 1, 1, 1200);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (shift, [0; 6]),
+                (shift, keys(KC::Aa)),
+                (0, keys(KC::Aa)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6])
+            ]
+        );
+
+        // cancelled after the timeout
+        tap(&mut engine, 1, 0, 2000);
+        block_on(engine.tick(Instant::from_millis(2000) + ONESHOT_TIMEOUT));
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(shift, [0; 6]), (0, [0; 6])]
+        );
+    }
+
+    #[test]
+    fn oneshot_layer_applies_to_the_next_key_and_locks_on_double_tap() {
+        let mut keymap = keymap(&[(1, 0, KC::OSL1), (1, 1, KC::Bb)]);
+        keymap[1][1][0] = KC::OSL1;
+        keymap[1][1][1] = KC::LeftArr;
+        let mut engine = engine(keymap);
+
+        tap(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        tap(&mut engine, 1, 1, 1200);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+
+        // locked until tapped again
+        tap(&mut engine, 1, 0, 2000);
+        tap(&mut engine, 1, 0, 2100);
+        tap(&mut engine, 1, 1, 2200);
+        tap(&mut engine, 1, 1, 2300);
+        tap(&mut engine, 1, 0, 2400);
+        tap(&mut engine, 1, 1, 2500);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn tap_dance_sends_the_output_of_the_taps() {
+        let mut engine = engine(keymap(&[(1, 0, KC::TD1)]));
+
+        tap(&mut engine, 1, 0, 1000);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        block_on(engine.tick(Instant::from_millis(1010) + TAPPING_TERM));
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::Escape)), (0, [0; 6])]
+        );
+
+        tap(&mut engine, 1, 0, 2000);
+        tap(&mut engine, 1, 0, 2100);
+        block_on(engine.tick(Instant::from_millis(2110) + TAPPING_TERM));
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::CapsLock)), (0, [0; 6])]
+        );
+    }
+
+    #[test]
+    fn macro_types_its_steps() {
+        let mut engine = engine(keymap(&[(1, 0, KC::MA1)]));
+        let shift = KC::LShift.get_modifier();
+
+        tap(&mut engine, 1, 0, 1000);
+        let reports = keyboard_reports(&mut engine);
+
+        // git commit -m "" with the cursor between the quotes
+        assert_eq!(reports[..2], [(0, keys(KC::Gg)), (0, [0; 6])]);
+        assert!(reports.contains(&(shift, keys(KC::Quote))));
+        assert_eq!(
+            reports[reports.len() - 2..],
+            [(0, keys(KC::LeftArr)), (0, [0; 6])]
+        );
+    }
+
+    #[test]
+    fn media_key_sends_a_consumer_report() {
+        let mut engine = engine(keymap(&[(1, 0, KC::MeVolUp)]));
+
+        tap(&mut engine, 1, 0, 1000);
+        let usages: std::vec::Vec<u16> = engine
+            .host_mut()
+            .reports
+            .drain(..)
+            .filter_map(|report| match report {
+                HidReport::Consumer(report) => Some(report.usage_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(usages, [KC::MeVolUp.get_consumer_usage(), 0]);
+    }
+
+    #[test]
+    fn nkro_reports_the_keys_in_a_bitmap() {
+        let keymap = keymap(&[(1, 0, KC::NkTog), (1, 1, KC::Aa), (1, 2, KC::Bb)]);
+        let mut engine = engine(keymap);
+
+        tap(&mut engine, 1, 0, 1000);
+        press(&mut engine, 1, 1, 1100);
+        press(&mut engine, 1, 2, 1200);
+        let nkro_keys: std::vec::Vec<[u8; NKRO_USAGES / 8]> = engine
+            .host_mut()
+            .reports
+            .drain(..)
+            .filter_map(|report| match report {
+                HidReport::Nkro(report) => Some(report.keys),
+                _ => None,
+            })
+            .collect();
+
+        let bitmap = |kcs: &[KC]| {
+            let mut keys = [0; NKRO_USAGES / 8];
+            for kc in kcs {
+                keys[kc.code() as usize / 8] |= 1 << (kc.code() % 8);
+            }
+            keys
+        };
+        assert_eq!(nkro_keys, [bitmap(&[KC::Aa]), bitmap(&[KC::Aa, KC::Bb])]);
+    }
+
+    #[test]
+    fn error_rollover_while_more_than_six_keys_are_pressed() {
+        let letters = [KC::Aa, KC::Bb, KC::Cc, KC::Dd, KC::Ee, KC::Ff, KC::Gg];
+        let keys_map: std::vec::Vec<(u8, u8, KC)> = letters
+            .iter()
+            .enumerate()
+            .map(|(col, &kc)| (0, col as u8, kc))
+            .collect();
+        let mut engine = engine(keymap(&keys_map));
+
+        for col in 0..7 {
+            press(&mut engine, 0, col, 1000 + col as u64 * 10);
+        }
+        let reports = keyboard_reports(&mut engine);
+        assert_eq!(reports.last(), Some(&(0, [KC::ERO.code() as u8; 6])));
+
+        // the overflowed key takes the place of the released one
+        release(&mut engine, 0, 0, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(
+                0,
+                [KC::Gg, KC::Bb, KC::Cc, KC::Dd, KC::Ee, KC::Ff].map(|kc: KC| kc.code() as u8)
+            )]
+        );
+
+        // the matrix reports a rollover of the half
+        let event = KeyEvent::Rollover {
+            split: false,
+            active: true,
+            time: Instant::from_millis(1200),
+        };
+        block_on(engine.event(event));
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, [KC::ERO.code() as u8; 6])]
+        );
+    }
+
+    #[test]
+    fn error_rollover_while_a_half_has_no_free_slot() {
+        let mut engine = engine(keymap(&[(3, 4, KC::Aa)]));
+
+        // no-op keys fill the slots of the left half
+        for index in 0..MATRIX_KEYS_BUFFER as u8 {
+            press(&mut engine, index / 5, index % 5, 1000 + index as u64);
+        }
+        assert_eq!(keyboard_reports(&mut engine), []);
+
+        // the press of a is lost, the keyboard reports it
+        press(&mut engine, 3, 4, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, [KC::ERO.code() as u8; 6])]
+        );
+
+        release(&mut engine, 3, 4, 1200);
+        assert_eq!(keyboard_reports(&mut engine), [(0, [0; 6])]);
+    }
+
+    #[test]
+    fn split_keys_released_when_the_split_link_goes_down() {
+        let mut engine = engine(keymap(&[(1, 1, KC::Oo), (1, 6, KC::Hh)]));
+
+        press(&mut engine, 1, 1, 1000);
+        press(&mut engine, 1, 6, 1010);
+        keyboard_reports(&mut engine);
+
+        // the local key stays pressed
+        block_on(engine.release_split(Instant::from_millis(1100)));
+        assert_eq!(keyboard_reports(&mut engine), [(0, keys(KC::Oo))]);
+
+        // the release sent after the link is back finds no key
+        release(&mut engine, 1, 6, 1200);
+        release(&mut engine, 1, 1, 1210);
+        assert_eq!(keyboard_reports(&mut engine), [(0, [0; 6])]);
+    }
+
+    #[test]
+    fn tap_hold_held_past_the_term_is_a_hold_without_a_tick() {
+        let mut engine = engine(keymap(&[(1, 0, KC::TH1)]));
+        let ctrl = KC::LCtrl.get_modifier();
+
+        // the release comes in before the tick at the end of the tapping term
+        press(&mut engine, 1, 0, 1000);
+        release(&mut engine, 1, 0, 1010 + TAPPING_TERM.as_millis());
+        assert_eq!(keyboard_reports(&mut engine), [(ctrl, [0; 6]), (0, [0; 6])]);
+    }
+
+    #[test]
+    fn combo_sends_its_chord() {
+        let mut engine = combo_engine();
+        let ctrl = KC::LCtrl.get_modifier();
+
+        press(&mut engine, 2, 0, 1000);
+        press(&mut engine, 1, 5, 1010);
+        assert_eq!(keyboard_reports(&mut engine), [(ctrl, keys(KC::Backspace))]);
+
+        // released once all combo keys are released
+        release(&mut engine, 2, 0, 1100);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        release(&mut engine, 1, 5, 1110);
+        assert_eq!(keyboard_reports(&mut engine), [(0, [0; 6])]);
+    }
+
+    #[test]
+    fn combo_key_released_early_is_sent_as_a_plain_key() {
+        let mut engine = combo_engine();
+
+        press(&mut engine, 1, 5, 1000);
+        press(&mut engine, 1, 6, 1010);
+        assert_eq!(keyboard_reports(&mut engine), []);
+
+        // the combo can't complete anymore, the held back keys are sent in order
+        release(&mut engine, 1, 5, 1020);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::Dd)), (0, [0; 6]), (0, keys(KC::Hh))]
+        );
+        assert_eq!(engine.deadline(), None);
+    }
+
+    #[test]
+    fn combo_key_is_sent_after_the_timeout() {
+        let mut engine = combo_engine();
+
+        press(&mut engine, 2, 0, 1000);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        assert_eq!(engine.deadline(), Some(Instant::from_millis(1050)));
+
+        block_on(engine.tick(Instant::from_millis(1050)));
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(KC::LCtrl.get_modifier(), [0; 6])]
+        );
+    }
+
+    #[test]
+    fn bootloader_key_held() {
+        let mut engine = engine(keymap(&[(0, 0, KC::Quote)]));
+
+        press(&mut engine, 0, 0, 1000);
+        release(&mut engine, 0, 0, 2000);
+        assert!(!engine.host().bootloader);
+
+        press(&mut engine, 0, 0, 3000);
+        release(&mut engine, 0, 0, 3000 + BOOTLOADER_HOLD.as_millis());
+        assert!(engine.host().bootloader);
+    }
+
+    #[test]
+    fn caps_word_shifts_the_letters_of_a_word() {
+        let keymap = keymap(&[
+            (1, 0, KC::CapsWord),
+            (1, 1, KC::Aa),
+            (1, 2, KC::Dash),
+            (1, 3, KC::K1),
+            (1, 4, KC::Space),
+            (0, 1, LS(KC::Dash)),
+        ]);
+        let mut engine = engine(keymap);
+        let shift = KC::LShift.get_modifier();
+
+        tap(&mut engine, 1, 0, 1000);
+        assert_eq!(keyboard_reports(&mut engine), []);
+
+        tap(&mut engine, 1, 1, 1100);
+        tap(&mut engine, 1, 2, 1200);
+        tap(&mut engine, 1, 3, 1300);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (shift, keys(KC::Aa)),
+                (shift, [0; 6]),
+                (shift, keys(KC::Dash)),
+                (shift, [0; 6]),
+                (0, keys(KC::K1)),
+                (0, [0; 6])
+            ]
+        );
+
+        // the underscore is a modified key that continues the word
+        tap(&mut engine, 0, 1, 1350);
+        tap(&mut engine, 1, 1, 1370);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (shift, keys(KC::Dash)),
+                (shift, [0; 6]),
+                (shift, keys(KC::Aa)),
+                (shift, [0; 6])
+            ]
+        );
+
+        // the space ends the word
+        tap(&mut engine, 1, 4, 1400);
+        tap(&mut engine, 1, 1, 1500);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Space)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn caps_word_times_out() {
+        let keymap = keymap(&[(1, 0, KC::CapsWord), (1, 1, KC::Aa)]);
+        let mut engine = engine(keymap);
+        let shift = KC::LShift.get_modifier();
+
+        tap(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(shift, keys(KC::Aa)), (shift, [0; 6])]
+        );
+
+        let timeout = Instant::from_millis(1100) + CAPS_WORD_TIMEOUT;
+        assert_eq!(engine.deadline(), Some(timeout));
+        block_on(engine.tick(timeout));
+        assert_eq!(keyboard_reports(&mut engine), [(0, [0; 6])]);
+    }
+
+    #[test]
+    fn key_override_replaces_the_key_without_its_modifier() {
+        let keymap = keymap(&[(1, 0, KC::LShift), (1, 1, KC::Backspace), (1, 2, KC::Aa)]);
+        let mut engine = engine(keymap);
+        let shift = KC::LShift.get_modifier();
+
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(shift, [0; 6]), (0, keys(KC::Delete)), (shift, [0; 6])]
+        );
+
+        // an other key press while the replacement is held is not sent with the modifier
+        press(&mut engine, 1, 1, 1200);
+        press(&mut engine, 1, 2, 1210);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Delete)),
+                (
+                    0,
+                    [KC::Delete.code() as u8, KC::Aa.code() as u8, 0, 0, 0, 0]
+                )
+            ]
+        );
+
+        // the modifier is sent again once the replacement is released
+        release(&mut engine, 1, 1, 1220);
+        release(&mut engine, 1, 2, 1230);
+        release(&mut engine, 1, 0, 1240);
+        tap(&mut engine, 1, 1, 1300);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (shift, [0, KC::Aa.code() as u8, 0, 0, 0, 0]),
+                (shift, [0; 6]),
+                (0, [0; 6]),
+                (0, keys(KC::Backspace)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn key_override_matches_the_declared_side() {
+        let key_override = KeyOverride {
+            mods: &[KC::RShift],
+            key: KC::Backspace,
+            replacement: KC::Delete,
+            layers: ALL_LAYERS,
+        };
+        let lshift = KC::LShift.get_modifier();
+        let rshift = KC::RShift.get_modifier();
+
+        assert_eq!(key_override.modifier(), rshift);
+        assert!(key_override.matches(&KC::Backspace, rshift, 0));
+        assert!(!key_override.matches(&KC::Backspace, lshift, 0));
+        assert!(!key_override.matches(&KC::Backspace, 0, 0));
+
+        // both sides declared, either one matches
+        let key_override = KeyOverride {
+            mods: EITHER_SHIFT,
+            ..key_override
+        };
+        assert!(key_override.matches(&KC::Backspace, rshift, 0));
+        assert!(key_override.matches(&KC::Backspace, lshift, 0));
+        assert!(key_override.matches(&KC::Backspace, lshift | rshift, 0));
+        assert!(!key_override.matches(&KC::Backspace, 0, 0));
+    }
+
+    #[test]
+    fn modified_key_keeps_the_held_modifiers() {
+        let keymap = keymap(&[(1, 0, KC::LCtrl), (1, 1, LS(KC::K9)), (1, 2, KC::Aa)]);
+        let mut engine = engine(keymap);
+        let ctrl = KC::LCtrl.get_modifier();
+        let shift = KC::LShift.get_modifier();
+
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(ctrl, [0; 6]), (ctrl | shift, keys(KC::K9)), (ctrl, [0; 6])]
+        );
+
+        // an other key press releases the modifiers of the modified key
+        release(&mut engine, 1, 0, 1200);
+        press(&mut engine, 1, 1, 1300);
+        press(&mut engine, 1, 2, 1310);
+        release(&mut engine, 1, 1, 1320);
+        release(&mut engine, 1, 2, 1330);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, [0; 6]),
+                (shift, keys(KC::K9)),
+                (0, [KC::K9.code() as u8, KC::Aa.code() as u8, 0, 0, 0, 0]),
+                (0, [0, KC::Aa.code() as u8, 0, 0, 0, 0]),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn right_and_compound_modifiers() {
+        let keymap = keymap(&[(1, 0, KC::RShift), (1, 1, KC::RGUI), (1, 2, KC::Hyper)]);
+        let mut engine = engine(keymap);
+
+        press(&mut engine, 1, 0, 1000);
+        press(&mut engine, 1, 1, 1010);
+        release(&mut engine, 1, 0, 1020);
+        press(&mut engine, 1, 2, 1030);
+        release(&mut engine, 1, 1, 1040);
+        release(&mut engine, 1, 2, 1050);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0x20, [0; 6]),
+                (0xA0, [0; 6]),
+                (0x80, [0; 6]),
+                (0x8F, [0; 6]),
+                (0x0F, [0; 6]),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn leader_sequence_is_sent_instead_of_its_keys() {
+        let keymap = keymap(&[
+            (1, 0, KC::Leader),
+            (1, 1, KC::Ss),
+            (1, 2, KC::Gg),
+            (1, 3, KC::Aa),
+        ]);
+        let mut engine = engine(keymap);
+
+        tap(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(KC::LCtrl.get_modifier(), keys(KC::Ss)), (0, [0; 6])]
+        );
+
+        // g may be followed by an other key, the sequence ends without output after the timeout
+        tap(&mut engine, 1, 0, 2000);
+        tap(&mut engine, 1, 2, 2100);
+        assert_eq!(
+            engine.deadline(),
+            Some(Instant::from_millis(2100) + LEADER_TIMEOUT)
+        );
+        block_on(engine.tick(Instant::from_millis(2100) + LEADER_TIMEOUT));
+        tap(&mut engine, 1, 3, 3000);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::Aa)), (0, [0; 6])]
+        );
+    }
+
+    #[test]
+    fn auto_shift_on_long_press() {
+        let keymap = keymap(&[(1, 0, KC::AsTog), (1, 1, KC::Aa), (1, 2, KC::K1)]);
+        let mut engine = engine(keymap);
+        let shift = KC::LShift.get_modifier();
+
+        tap(&mut engine, 1, 0, 1000);
+
+        // a tap is sent on the release, a rolled key after it
+        press(&mut engine, 1, 1, 1100);
+        press(&mut engine, 1, 2, 1120);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        release(&mut engine, 1, 1, 1140);
+        release(&mut engine, 1, 2, 1150);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Aa)),
+                (0, [0; 6]),
+                (0, keys(KC::K1)),
+                (0, [0; 6])
+            ]
+        );
+
+        // held past the term
+        press(&mut engine, 1, 1, 2000);
+        let term = Instant::from_millis(2000) + AUTO_SHIFT_TERM;
+        assert_eq!(engine.deadline(), Some(term));
+        block_on(engine.tick(term));
+        release(&mut engine, 1, 1, 2500);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(shift, keys(KC::Aa)), (0, [0; 6])]
+        );
+    }
+
+    #[test]
+    fn auto_shift_of_a_letter_typed_twice() {
+        let keymap = keymap(&[(1, 0, KC::AsTog), (1, 1, KC::Aa), (1, 2, KC::Bb)]);
+        let mut engine = engine(keymap);
+
+        tap(&mut engine, 1, 0, 1000);
+
+        // "aa" typed while b waits for its term, each a is sent
+        press(&mut engine, 1, 2, 1100);
+        tap(&mut engine, 1, 1, 1110);
+        tap(&mut engine, 1, 1, 1130);
+        assert_eq!(keyboard_reports(&mut engine), []);
+        release(&mut engine, 1, 2, 1150);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Bb)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn repeat_keys_send_the_last_key() {
+        let keymap = keymap(&[
+            (1, 0, KC::LCtrl),
+            (1, 1, KC::LeftArr),
+            (1, 2, KC::Repeat),
+            (1, 3, KC::AltRepeat),
+        ]);
+        let mut engine = engine(keymap);
+        let ctrl = KC::LCtrl.get_modifier();
+
+        // nothing to repeat yet
+        tap(&mut engine, 1, 2, 1000);
+        assert_eq!(keyboard_reports(&mut engine), []);
+
+        tap(&mut engine, 1, 1, 1100);
+        tap(&mut engine, 1, 2, 1200);
+        tap(&mut engine, 1, 3, 1300);
+        tap(&mut engine, 1, 3, 1400);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::LeftArr)),
+                (0, [0; 6]),
+                (0, keys(KC::RightArr)),
+                (0, [0; 6]),
+                (0, keys(KC::RightArr)),
+                (0, [0; 6])
+            ]
+        );
+
+        // the modifiers are repeated after they are released
+        press(&mut engine, 1, 0, 2000);
+        tap(&mut engine, 1, 1, 2100);
+        release(&mut engine, 1, 0, 2200);
+        keyboard_reports(&mut engine);
+        tap(&mut engine, 1, 3, 2300);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(ctrl, keys(KC::RightArr)), (0, [0; 6])]
+        );
+    }
+
+    #[test]
+    fn tri_layer_while_both_layers_are_held() {
+        let mut keymap = keymap(&[(1, 0, KC::L1), (1, 1, KC::L2)]);
+        keymap[1][1][1] = KC::Trans;
+        keymap[2][1][0] = KC::Trans;
+        keymap[1][1][2] = KC::Bb;
+        keymap[3][1][2] = KC::Aa;
+        let mut engine = engine(keymap);
+
+        press(&mut engine, 1, 0, 1000);
+        press(&mut engine, 1, 1, 1100);
+        tap(&mut engine, 1, 2, 1200);
+        release(&mut engine, 1, 1, 1300);
+        tap(&mut engine, 1, 2, 1400);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Aa)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn layer_lock_keeps_the_held_layer_on() {
+        let mut keymap = keymap(&[(1, 0, KC::L1), (1, 2, KC::Aa)]);
+        keymap[1][1][1] = KC::LayerLock;
+        keymap[1][1][2] = KC::Bb;
+        let mut engine = engine(keymap);
+
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        release(&mut engine, 1, 0, 1200);
+        tap(&mut engine, 1, 2, 1300);
+
+        // pressed again, the layer is unlocked
+        tap(&mut engine, 1, 1, 1400);
+        tap(&mut engine, 1, 2, 1500);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Bb)),
+                (0, [0; 6]),
+                (0, keys(KC::Aa)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn layer_lock_keeps_the_one_shot_locked_layers() {
+        let mut keymap = keymap(&[(1, 0, KC::OSL2), (1, 1, KC::L3), (1, 3, KC::Aa)]);
+        keymap[2][1][0] = KC::Trans;
+        keymap[2][1][1] = KC::Trans;
+        keymap[2][1][3] = KC::Bb;
+        keymap[3][1][2] = KC::LayerLock;
+        keymap[3][1][3] = KC::Cc;
+        let mut engine = engine(keymap);
+
+        // layer 2 locked by a double tap, layer 3 by the layer lock key
+        tap(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 0, 1100);
+        press(&mut engine, 1, 1, 1200);
+        tap(&mut engine, 1, 2, 1300);
+        release(&mut engine, 1, 1, 1400);
+        tap(&mut engine, 1, 3, 1500);
+
+        // the layer lock key unlocks layer 3 only
+        tap(&mut engine, 1, 2, 1600);
+        tap(&mut engine, 1, 3, 1700);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [
+                (0, keys(KC::Cc)),
+                (0, [0; 6]),
+                (0, keys(KC::Bb)),
+                (0, [0; 6])
+            ]
+        );
+    }
+
+    #[test]
+    fn layer_to_releases_the_held_layer_keys() {
+        let mut keymap = keymap(&[(1, 0, KC::L2), (1, 3, KC::Aa)]);
+        keymap[2][1][1] = KC::TO2;
+        keymap[2][1][3] = KC::Cc;
+        let mut engine = engine(keymap);
+
+        // the release of the layer key keeps the layer the to-layer key turned on
+        press(&mut engine, 1, 0, 1000);
+        tap(&mut engine, 1, 1, 1100);
+        release(&mut engine, 1, 0, 1200);
+        tap(&mut engine, 1, 3, 1300);
+        assert_eq!(
+            keyboard_reports(&mut engine),
+            [(0, keys(KC::Cc)), (0, [0; 6])]
+        );
+    }
+}
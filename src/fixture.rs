@@ -0,0 +1,187 @@
+//! Fixture of the engine and trace tests.
+//!
+//! The tests run with their own keymap and tables, so an edit of the keyboard `config` does not
+//! change them. The tables have the entries the tests use, e.g. `KC::TH1` is a / ctrl.
+
+use embassy_futures::block_on;
+use embassy_time::Instant;
+
+use crate::{
+    combo::{ALL_LAYERS, Combo, ComboAction, ComboRelease},
+    config::{COMBO_TIMEOUT, KEYMAP_COLS, LAYERS, ROWS, TAPPING_TERM},
+    engine::{Engine, EngineConfig, HidReport},
+    key_override::KeyOverride,
+    keycodes::{HoldFlavor, KC, TapDance, TapHold},
+    layer::LayerCondition,
+    leader::LeaderSequence,
+    macros::MacroStep,
+    matrix::{KeyEvent, KeyPos},
+    trace::TraceHost,
+};
+
+/// `KC::TH1`: a / ctrl, `KC::TH2`: space / layer 1, `KC::TH3`: e / gui, tap-preferred
+pub const TAP_HOLD_KEYS: &[TapHold] = &[
+    TapHold {
+        tap: KC::Aa,
+        hold: KC::LCtrl,
+        flavor: HoldFlavor::Balanced,
+        tapping_term: TAPPING_TERM,
+    },
+    TapHold {
+        tap: KC::Space,
+        hold: KC::L1,
+        flavor: HoldFlavor::HoldPreferred,
+        tapping_term: TAPPING_TERM,
+    },
+    TapHold {
+        tap: KC::Ee,
+        hold: KC::LGUI,
+        flavor: HoldFlavor::TapPreferred,
+        tapping_term: TAPPING_TERM,
+    },
+];
+
+/// `KC::TD1`: escape / caps lock
+pub const TAP_DANCE_KEYS: &[TapDance] = &[TapDance {
+    taps: [KC::Escape, KC::CapsLock, KC::NoOp],
+    hold: KC::NoOp,
+    tap_hold: KC::NoOp,
+    tapping_term: TAPPING_TERM,
+}];
+
+/// `KC::MA1`: git commit -m "" with the cursor between the quotes
+pub const MACROS: &[&[MacroStep]] = &[&[
+    MacroStep::Type("git commit -m \"\""),
+    MacroStep::Tap(KC::LeftArr),
+]];
+
+/// Ctrl (2 0) + d (1 5) => ctrl + backspace, only in the configs of the combo tests
+pub const COMBOS: &[Combo] = &[Combo {
+    keys: &[KeyPos { row: 2, col: 0 }, KeyPos { row: 1, col: 5 }],
+    action: ComboAction::Chord(&[KC::LCtrl, KC::Backspace]),
+    timeout: COMBO_TIMEOUT,
+    layers: 1 << 0,
+    release: ComboRelease::AllReleased,
+}];
+
+/// Shift + backspace => delete
+pub const KEY_OVERRIDES: &[KeyOverride] = &[KeyOverride {
+    mods: &[KC::LShift],
+    key: KC::Backspace,
+    replacement: KC::Delete,
+    layers: ALL_LAYERS,
+}];
+
+/// Leader g c => `KC::MA1`, leader s => ctrl + s
+pub const LEADER_SEQUENCES: &[LeaderSequence] = &[
+    LeaderSequence {
+        keys: &[KC::Gg, KC::Cc],
+        action: ComboAction::Key(KC::MA1),
+    },
+    LeaderSequence {
+        keys: &[KC::Ss],
+        action: ComboAction::Chord(&[KC::LCtrl, KC::Ss]),
+    },
+];
+
+/// Layer 1 + layer 2 => layer 3
+pub const LAYER_CONDITIONS: &[LayerCondition] = &[LayerCondition {
+    if_layers: (1 << 1) | (1 << 2),
+    then_layer: 3,
+}];
+
+pub const ALT_REPEAT_KEYS: &[(KC, KC)] = &[(KC::LeftArr, KC::RightArr)];
+
+/// Keymap with the given keys on layer 0, the other keys are no-op
+pub fn keymap(keys: &[(u8, u8, KC)]) -> [[[KC; KEYMAP_COLS]; ROWS]; LAYERS] {
+    let mut keymap = [[[KC::NoOp; KEYMAP_COLS]; ROWS]; LAYERS];
+    for &(row, col, kc) in keys {
+        keymap[0][row as usize][col as usize] = kc;
+    }
+    keymap
+}
+
+/// Config of the tests with a keymap, without combos
+pub fn config(keymap: [[[KC; KEYMAP_COLS]; ROWS]; LAYERS]) -> EngineConfig {
+    EngineConfig {
+        keymap,
+        tap_hold_keys: TAP_HOLD_KEYS,
+        tap_dance_keys: TAP_DANCE_KEYS,
+        macros: MACROS,
+        combos: &[],
+        key_overrides: KEY_OVERRIDES,
+        leader_sequences: LEADER_SEQUENCES,
+        layer_conditions: LAYER_CONDITIONS,
+        alt_repeat_keys: ALT_REPEAT_KEYS,
+        auto_shift_excluded: &[],
+        nkro: false,
+        auto_shift: false,
+    }
+}
+
+/// Config of the traces in `traces/`, with the keys they were recorded on
+pub fn trace_config() -> EngineConfig {
+    let mut keymap = keymap(&[
+        (0, 0, KC::Quote),
+        (1, 1, KC::Oo),
+        (1, 5, KC::Dd),
+        (1, 6, KC::Hh),
+        (2, 0, KC::LCtrl),
+        (3, 7, KC::L1),
+    ]);
+    keymap[1][1][1] = KC::K4;
+
+    EngineConfig {
+        combos: COMBOS,
+        ..config(keymap)
+    }
+}
+
+/// Engine with the test config of a keymap
+pub fn engine(keymap: [[[KC; KEYMAP_COLS]; ROWS]; LAYERS]) -> Engine<TraceHost> {
+    Engine::with_config(TraceHost::default(), config(keymap))
+}
+
+/// Engine with the `COMBOS`, ctrl (2 0), d (1 5) and h (1 6)
+pub fn combo_engine() -> Engine<TraceHost> {
+    let keymap = keymap(&[(2, 0, KC::LCtrl), (1, 5, KC::Dd), (1, 6, KC::Hh)]);
+    let config = EngineConfig {
+        combos: COMBOS,
+        ..config(keymap)
+    };
+    Engine::with_config(TraceHost::default(), config)
+}
+
+pub fn press(engine: &mut Engine<TraceHost>, row: u8, col: u8, ms: u64) {
+    let event = KeyEvent::Pressed(KeyPos { row, col }, Instant::from_millis(ms));
+    block_on(engine.event(event));
+}
+
+pub fn release(engine: &mut Engine<TraceHost>, row: u8, col: u8, ms: u64) {
+    let event = KeyEvent::Released(KeyPos { row, col }, Instant::from_millis(ms));
+    block_on(engine.event(event));
+}
+
+/// Press a key and release it 10 ms later
+pub fn tap(engine: &mut Engine<TraceHost>, row: u8, col: u8, ms: u64) {
+    press(engine, row, col, ms);
+    release(engine, row, col, ms + 10);
+}
+
+/// Take the modifier and keycodes of the keyboard reports sent so far
+pub fn keyboard_reports(engine: &mut Engine<TraceHost>) -> Vec<(u8, [u8; 6])> {
+    engine
+        .host_mut()
+        .reports
+        .drain(..)
+        .filter_map(|report| match report {
+            HidReport::Keyboard(report) => Some((report.modifier, report.keycodes)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Keycodes of a report with a single key
+pub fn keys(kc: KC) -> [u8; 6] {
+    [kc.code() as u8, 0, 0, 0, 0, 0]
+}
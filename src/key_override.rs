@@ -0,0 +1,42 @@
+use crate::keycodes::KC;
+
+/// Modifiers of `KeyOverride::mods` matched by either shift key
+pub const EITHER_SHIFT: &[KC] = &[KC::LShift, KC::RShift];
+
+/// Key pressed while the modifiers are active sends the replacement instead (e.g. shift + backspace => delete),
+/// the modifiers are suppressed while the replacement is held
+#[derive(Debug, Clone, Copy)]
+pub struct KeyOverride {
+    /// Modifiers that all have to be active on the declared side, a modifier declared on both
+    /// sides is matched by either one (e.g. `EITHER_SHIFT`)
+    pub mods: &'static [KC],
+    pub key: KC,
+    pub replacement: KC,
+    /// Bitmask of the layers the key override is active on
+    pub layers: u32,
+}
+
+impl KeyOverride {
+    /// Modifier bits of the override modifiers
+    pub fn modifier(&self) -> u8 {
+        self.mods
+            .iter()
+            .fold(0, |modifier, kc| modifier | kc.get_modifier())
+    }
+
+    /// Check if the override applies to a key pressed with the active modifiers on a layer
+    pub fn matches(&self, kc: &KC, modifier: u8, layer: u8) -> bool {
+        let mods = self.modifier();
+
+        // the modifiers declared on both sides, in the left bits
+        let either = mods & (mods >> 4) & 0x0F;
+        let exact = mods & !(either | either << 4);
+        let active = (modifier | modifier >> 4) & 0x0F;
+
+        *kc == self.key
+            && self.layers & (1 << layer) != 0
+            && mods != 0
+            && modifier & exact == exact
+            && active & either == either
+    }
+}
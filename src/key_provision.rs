@@ -1,404 +1,151 @@
+#[cfg(any(feature = "peripheral", feature = "central"))]
+use core::sync::atomic::Ordering;
 #[cfg(feature = "defmt")]
 use defmt::info;
 #[cfg(feature = "peripheral")]
-use embassy_futures::select::{Either, select};
+use embassy_futures::select::{Either3, select3};
+use embassy_time::Instant;
 #[cfg(feature = "peripheral")]
-use usbd_hid::descriptor::KeyboardReport;
+use embassy_time::{Duration, Timer};
 
 #[cfg(feature = "peripheral")]
 use crate::{
-    KEY_REPORT, MATRIX_KEYS_SPLIT,
-    config::provide_keymap,
-    config::{KEYMAP_COLS, LAYERS, ROWS},
-    keycodes::KeyType,
+    HID_REPORTS, HOST_CONNECTED, SPLIT_DISCONNECTED,
+    engine::{Engine, HidReport, Host},
 };
 
 #[cfg(feature = "central")]
-use crate::MESSAGE_TO_PERI;
-
 use crate::{
-    MATRIX_KEYS_LOCAL,
-    config::{MATRIX_KEYS_BUFFER, MATRIX_KEYS_COMB_BUFFER},
-    keycodes::KC,
-    matrix::{Key, KeyPos, KeyState},
+    MESSAGE_TO_PERI, PERI_CONNECTED,
+    engine::{BOOTLOADER_HOLD, BOOTLOADER_KEY},
+    matrix::KeyEvent,
 };
-use embassy_time::{Duration, Instant};
-use heapless::Vec;
 
-pub struct KeyProvision {
-    #[cfg(feature = "peripheral")]
-    layer: u8,
-    #[cfg(feature = "peripheral")]
-    keymap: [[[KC; KEYMAP_COLS]; ROWS]; LAYERS],
-    #[cfg(feature = "peripheral")]
-    keyreport_local: KeyboardReport,
-    #[cfg(feature = "central")]
-    message_to_peri_local: [u8; 6],
-    #[cfg(feature = "central")]
-    message_to_peri_local_old: [u8; 6],
-}
+use crate::KEY_EVENTS;
 
-impl KeyProvision {
-    pub fn init() -> Self {
-        Self {
-            #[cfg(feature = "peripheral")]
-            layer: 0,
-            #[cfg(feature = "peripheral")]
-            keymap: provide_keymap(),
-            #[cfg(feature = "peripheral")]
-            keyreport_local: KeyboardReport::default(),
-
-            #[cfg(feature = "central")]
-            message_to_peri_local: [255; 6],
-            #[cfg(feature = "central")]
-            message_to_peri_local_old: [255; 6],
-        }
-    }
-    #[cfg(feature = "peripheral")]
-    pub async fn provision_pressed_keys(&mut self, kc: &KC) {
-        // get the key type
-        match KeyType::check_type(kc) {
-            // KeyType::Macro => {
-            //     let macro_valid_keys = Kc::get_macro_sequence(hid_key);
-            //     for valid_key in macro_valid_keys.iter() {
-            //         add_keys_master(keyboard_key_report, mouse_key_report, valid_key, layer);
-            //     }
-            // }
-            KeyType::Layer => {
-                // check and set the layer
-                self.layer = kc.get_layer();
-            }
-            KeyType::Modifier => {
-                self.keyreport_local.modifier |= kc.get_modifier();
-            }
-            // KeyType::Mouse => {
-            //     // set the mouse command to the mouse ble characteristic
-            //     mouse_key_report.set_command(hid_key);
-            // }
-            KeyType::Key => {
-                // check if the key count is less than 6
-                if !self.keyreport_local.keycodes.contains(&(*kc as u8)) {
-                    // find the first key slot in the array that is free
-                    if let Some(index) = self
-                        .keyreport_local
-                        .keycodes
-                        .iter()
-                        .position(|&value| value == 0)
-                    {
-                        // add the new key to that position
-                        self.keyreport_local.keycodes[index] = *kc as u8
-                    }
-                }
-            }
-
-            _ => {} // TODO: temporary
-        }
-    }
-
-    #[cfg(feature = "peripheral")]
-    async fn provision_released_keys(&mut self, kc: &KC) {
-        // get the key type
-        match KeyType::check_type(kc) {
-            //     KeyType::Macro => {
-            //         let macro_valid_keys = Kc::get_macro_sequence(hid_key);
-            //         for valid_key in macro_valid_keys.iter() {
-            //             remove_keys_master(keyboard_key_report, mouse_key_report, valid_key, layer);
-            //         }
-            //     }
-            KeyType::Layer => {
-                // set previous layer
-                self.layer -= 1;
-            }
-            KeyType::Modifier => {
-                // remove the modifier
-                self.keyreport_local.modifier &= !kc.get_modifier();
-            }
-            // KeyType::Mouse => {
-            //     // remove the mouse command from the mouse ble characteristic
-            //     mouse_key_report.reset_keypress(hid_key);
-            // }
-            KeyType::Key => {
-                // find the key index of the released key
-                if let Some(index) = self
-                    .keyreport_local
-                    .keycodes
-                    .iter()
-                    .position(|&value| value == *kc as u8)
-                {
-                    // remove the key from the keyreport_local
-                    self.keyreport_local.keycodes[index] = 0;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    async fn matrix_to_hid_local(
-        &self,
-        matrix_keys_local: &mut [Key; MATRIX_KEYS_COMB_BUFFER],
-        matrix_keys_received: &[KeyPos; MATRIX_KEYS_BUFFER],
-    ) {
-        for (index_received, key_pos_received) in matrix_keys_received.iter().enumerate() {
-            if *key_pos_received != KeyPos::default() {
-                #[cfg(feature = "defmt")]
-                info!(
-                    "[matrix_to_hid] matrix_keys_received: r{} c{}",
-                    key_pos_received.row, key_pos_received.col
-                );
-
-                // if new key is not contained, add it
-                if !matrix_keys_local
-                    .iter()
-                    .any(|key| key.position == *key_pos_received)
-                {
-                    let key = Key {
-                        #[cfg(feature = "peripheral")]
-                        code: self.keymap[self.layer as usize][key_pos_received.row as usize]
-                            [key_pos_received.col as usize],
+#[cfg(feature = "peripheral")]
+/// Host of the engine on the keyboard, the reports go to the ble hid service
+pub struct BleHost {
+    /// Reports dropped since the hid host is not connected
+    dropped_reports: u32,
+}
 
-                        #[cfg(feature = "central")]
-                        code: KC::Reserved,
-                        position: *key_pos_received,
-                        state: KeyState::Pressed,
-                        time: Instant::now(),
-                    };
+#[cfg(feature = "peripheral")]
+impl Host for BleHost {
+    async fn send(&mut self, report: HidReport) {
+        // the queue is only drained while connected, waiting would stop the key processing, the
+        // host gets an all-released report when it connects
+        if HOST_CONNECTED.load(Ordering::Relaxed) {
+            HID_REPORTS.send(report).await;
+        } else {
+            self.dropped_reports += 1;
 
-                    // set the new key in an empty slot
-                    matrix_keys_local[index_received] = key;
-                }
-            } else if matrix_keys_local[index_received].position != KeyPos::default() {
-                matrix_keys_local[index_received].state = KeyState::Released;
-            }
+            #[cfg(feature = "defmt")]
+            info!(
+                "[key_provision] reports dropped while not connected: {}",
+                self.dropped_reports
+            );
         }
     }
 
-    #[cfg(feature = "peripheral")]
-    async fn matrix_to_hid_split(
-        &self,
-        matrix_keys_local: &mut [Key; MATRIX_KEYS_COMB_BUFFER],
-        matrix_keys_received: &[KeyPos; MATRIX_KEYS_BUFFER],
-    ) {
-        for (index_received, key_pos_received) in matrix_keys_received.iter().enumerate() {
-            let index_received = index_received + MATRIX_KEYS_BUFFER;
-            if *key_pos_received != KeyPos::default() {
-                #[cfg(feature = "defmt")]
-                info!(
-                    "[matrix_to_hid] matrix_keys_received: r{} c{}",
-                    key_pos_received.row, key_pos_received.col
-                );
-
-                // if new key is not contained, add it
-                if !matrix_keys_local
-                    .iter()
-                    .any(|key| key.position == *key_pos_received)
-                {
-                    let key = Key {
-                        code: self.keymap[self.layer as usize][key_pos_received.row as usize]
-                            [key_pos_received.col as usize],
-                        position: *key_pos_received,
-                        state: KeyState::Pressed,
-                        time: Instant::now(),
-                    };
-
-                    // set the new key in an empty slot
-                    matrix_keys_local[index_received] = key;
-                }
-            } else if matrix_keys_local[index_received].position != KeyPos::default() {
-                matrix_keys_local[index_received].state = KeyState::Released;
-            }
-        }
+    async fn delay(&mut self, duration: Duration) {
+        Timer::after(duration).await;
     }
 
-    /// Evaluate if condition is met to enter bootloader
-    async fn evaluate_enter_bootloader(&self, key: &Key) {
-        if Instant::now() >= key.time + Duration::from_secs(5) {
-            let key_pos_enter_bl = KeyPos { row: 0, col: 0 };
-            if key.position == key_pos_enter_bl || key.code == KC::BTL {
-                // write to register to boot into BL
-                embassy_nrf::pac::POWER
-                    .gpregret()
-                    .write_value(embassy_nrf::pac::power::regs::Gpregret(0x57));
-
-                // reboot into bl
-                cortex_m::peripheral::SCB::sys_reset();
-            }
-        }
+    fn enter_bootloader(&mut self) {
+        enter_bootloader();
     }
+}
 
+pub struct KeyProvision {
     #[cfg(feature = "peripheral")]
-    /// Provision combo keys
-    async fn provision_combos(&mut self, matrix_keys_local: &mut [Key; MATRIX_KEYS_COMB_BUFFER]) {
-        let keys_to_remove: Vec<KC, { MATRIX_KEYS_COMB_BUFFER }> = Vec::from([KC::LCtrl, KC::Dd]);
-        let keys_to_add: Vec<KC, { MATRIX_KEYS_COMB_BUFFER }> =
-            Vec::from([KC::LCtrl, KC::Backspace]);
+    engine: Engine<BleHost>,
+    /// Press time of the bootloader key
+    #[cfg(feature = "central")]
+    bootloader_key_time: Option<Instant>,
+}
 
-        // check if all combo keys to remove are contained in the matrix keys
-        if keys_to_remove.iter().all(|remove_k| {
-            matrix_keys_local
-                .iter()
-                .any(|contained_k| *remove_k == contained_k.code)
-        }) {
-            for (index_rm, kc_rm) in keys_to_remove.iter().enumerate() {
-                if let Some(index) = matrix_keys_local.iter().position(|k| k.code == *kc_rm) {
-                    matrix_keys_local[index].code = keys_to_add[index_rm];
-                }
-            }
+impl KeyProvision {
+    pub fn init() -> Self {
+        Self {
+            #[cfg(feature = "peripheral")]
+            engine: Engine::new(BleHost { dropped_reports: 0 }),
+            #[cfg(feature = "central")]
+            bootloader_key_time: None,
         }
     }
 
     /// Main provision loop
     pub async fn run(&mut self) {
-        let mut matrix_keys_receiver = MATRIX_KEYS_LOCAL
-            .receiver()
-            .expect("[key_provision] unable to create matrix_key_local_receiver");
-        #[cfg(feature = "peripheral")]
-        let mut matrix_keys_split_receiver = MATRIX_KEYS_SPLIT
-            .receiver()
-            .expect("[key_provision] unable to create matrix_key_split_receiver");
-
-        #[cfg(feature = "peripheral")]
-        let key_report_sender = KEY_REPORT.sender();
-        #[cfg(feature = "central")]
-        let message_to_peri = MESSAGE_TO_PERI.sender();
-
-        let mut matrix_keys_local = [Key::default(); MATRIX_KEYS_COMB_BUFFER];
-        // let mut matrix_keys_local: Vec<Key, { MATRIX_KEYS_COMB_BUFFER }> = Vec::new();
-        let mut keys_to_remove: Vec<Key, { MATRIX_KEYS_COMB_BUFFER }> = Vec::new();
-
         loop {
+            // the events are processed one by one, so a fast tap is not lost
             #[cfg(feature = "peripheral")]
-            match select(
-                matrix_keys_receiver.changed(),
-                matrix_keys_split_receiver.changed(),
+            match select3(
+                KEY_EVENTS.receive(),
+                wait_deadline(self.engine.deadline()),
+                SPLIT_DISCONNECTED.wait(),
             )
             .await
             {
-                Either::First(matrix_keys_received) => {
-                    // transform the received local matrix keys
-                    self.matrix_to_hid_local(&mut matrix_keys_local, &matrix_keys_received)
-                        .await;
+                Either3::First(event) => self.engine.event(event).await,
+                Either3::Second(()) => {
+                    // a tap-hold, combo or one-shot key timed out
+                    self.engine.tick(Instant::now()).await;
                 }
-                Either::Second(matrix_keys_split_received) => {
-                    // transform the received split matrix keys
-                    self.matrix_to_hid_split(&mut matrix_keys_local, &matrix_keys_split_received)
-                        .await;
+                Either3::Third(()) => {
+                    // the releases of the central keys are lost with the link
+                    self.engine.release_split(Instant::now()).await;
                 }
             }
 
             #[cfg(feature = "central")]
             {
-                let matrix_keys_received = matrix_keys_receiver.changed().await;
-                self.matrix_to_hid_local(&mut matrix_keys_local, &matrix_keys_received)
-                    .await;
-            }
-
-            // provision combos
-            #[cfg(feature = "peripheral")]
-            self.provision_combos(&mut matrix_keys_local).await;
+                let event = KEY_EVENTS.receive().await;
 
-            #[cfg(feature = "defmt")]
-            info!(
-                "[key_provision] matrix_keys_local: {:#?}",
-                matrix_keys_local
-            );
-
-            // process the non default keys to keyreport
-            for key in matrix_keys_local
-                .iter_mut()
-                .filter(|key| key.code != KC::default())
-            {
-                match key.state {
-                    KeyState::Pressed => {
-                        #[cfg(feature = "peripheral")]
-                        // get the keycode
-                        self.provision_pressed_keys(&key.code).await;
+                #[cfg(feature = "defmt")]
+                info!("[key_provision] key event: {:?}", event);
 
-                        #[cfg(feature = "central")]
-                        {
-                            // set the row and shift 4 bits to left
-                            // append the col
-                            // row and col must be lower than 16 (fit in 4 bits)
-                            let key_to_send = (key.position.row << 4) | key.position.col;
+                // forward the event to the peripheral, waits while the queue is full so a release is
+                // not lost, the peripheral releases the keys of this half when the link goes down
+                if PERI_CONNECTED.load(Ordering::Relaxed) {
+                    MESSAGE_TO_PERI.send(event).await;
+                }
 
-                            if !self.message_to_peri_local.contains(&key_to_send)
-                                && let Some(index) = self
-                                    .message_to_peri_local
-                                    .iter_mut()
-                                    .position(|key| *key == 255)
-                            {
-                                self.message_to_peri_local[index] = key_to_send;
-                            }
-                        }
+                // evaluate enter_bootloader
+                match event {
+                    KeyEvent::Pressed(position, time) if position == BOOTLOADER_KEY => {
+                        self.bootloader_key_time = Some(time);
                     }
-                    KeyState::Released => {
-                        #[cfg(feature = "peripheral")]
-                        // remove the kc from keyreport_local
-                        self.provision_released_keys(&key.code).await;
-
-                        #[cfg(feature = "central")]
+                    KeyEvent::Released(position, _) if position == BOOTLOADER_KEY => {
+                        if let Some(time) = self.bootloader_key_time.take()
+                            && Instant::now() >= time + BOOTLOADER_HOLD
                         {
-                            // set the row and shift 4 bits to left
-                            // append the col
-                            // row and col must be lower than 16 (fit in 4 bits)
-                            let key_to_rm = (key.position.row << 4) | key.position.col;
-
-                            if let Some(index) = self
-                                .message_to_peri_local
-                                .iter_mut()
-                                .position(|key| *key == key_to_rm)
-                            {
-                                self.message_to_peri_local[index] = 255;
-                            }
+                            enter_bootloader();
                         }
-
-                        // evaluate enter_bootloader
-                        self.evaluate_enter_bootloader(key).await;
-
-                        // remember the key to be removed
-                        keys_to_remove
-                            .push(*key)
-                            .expect("[matrix] keys_to_remove is full");
                     }
+                    _ => {}
                 }
             }
+        }
+    }
+}
 
-            // remove the released keys
-            while let Some(key) = keys_to_remove.pop() {
-                #[cfg(feature = "defmt")]
-                info!("[key_provision] keys_to_remove key: {}", key.code as u8);
-                if let Some(position) = matrix_keys_local
-                    .iter()
-                    .position(|k| k.position == key.position)
-                {
-                    matrix_keys_local[position] = Key::default();
-                }
-            }
-
-            // send report
-            #[cfg(feature = "peripheral")]
-            {
-                key_report_sender.send(self.keyreport_local);
-
-                #[cfg(feature = "defmt")]
-                info!(
-                    "[key_provision] keyreport_local.keycodes: {:?}",
-                    self.keyreport_local.keycodes
-                );
-            }
-            #[cfg(feature = "central")]
-            {
-                if self.message_to_peri_local != self.message_to_peri_local_old {
-                    #[cfg(feature = "defmt")]
-                    info!(
-                        "[key_provision] message_to_peri_local: {:?}",
-                        self.message_to_peri_local
-                    );
+/// Reboot into the bootloader
+fn enter_bootloader() {
+    // write to register to boot into BL
+    embassy_nrf::pac::POWER
+        .gpregret()
+        .write_value(embassy_nrf::pac::power::regs::Gpregret(0x57));
 
-                    message_to_peri.send(self.message_to_peri_local);
+    // reboot into bl
+    cortex_m::peripheral::SCB::sys_reset();
+}
 
-                    self.message_to_peri_local_old = self.message_to_peri_local;
-                }
-            }
-        }
+#[cfg(feature = "peripheral")]
+/// Wait until the deadline is reached, forever if there is none
+async fn wait_deadline(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => Timer::at(deadline).await,
+        None => core::future::pending().await,
     }
 }
@@ -1,436 +1,879 @@
+use crate::macros::MacroStep;
 #[cfg(feature = "defmt")]
 use defmt::Format;
+use embassy_time::Duration;
 use usbd_hid::descriptor::KeyboardUsage;
 
-/// Short‑hand enum that mirrors every variant of `KeyboardUsage`.
-/// The discriminants are exactly the same HID usage codes, so you can use
-/// `KC` wherever the original values are required while keeping the terse names.
+/// Keycode with the modifiers pressed along with it (e.g. `LS(KC::K9)` for `(`).
+/// The keyboard keys have the same codes as the HID usages of `KeyboardUsage`,
+/// the codes from 0xF0 are the keys handled by the keyboard (layers, macros, ...)
 #[cfg_attr(feature = "defmt", derive(Format))]
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
-pub enum KC {
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct KC {
+    code: u16,
+    mods: u8,
+}
+
+/// Short‑hand names that mirror every variant of `KeyboardUsage`.
+/// The codes are exactly the same HID usage codes, so you can use
+/// `KC` wherever the original values are required while keeping the terse names.
+#[allow(non_upper_case_globals)]
+impl KC {
     // ------------------------------------------------------------------------
     // 0x00: Reserved
     /// Keyboard ErrorRollOver (Footnote 1)
-    ERO = KeyboardUsage::KeyboardErrorRollOver as isize,
+    pub const ERO: KC = KC::new(KeyboardUsage::KeyboardErrorRollOver as u16);
     /// Keyboard POSTFail (Footnote 1)
-    PF = KeyboardUsage::KeyboardPOSTFail as isize,
+    pub const PF: KC = KC::new(KeyboardUsage::KeyboardPOSTFail as u16);
     /// Keyboard ErrorUndefined (Footnote 1)
-    #[default]
-    EU = KeyboardUsage::KeyboardErrorUndefined as isize,
+    pub const EU: KC = KC::new(KeyboardUsage::KeyboardErrorUndefined as u16);
 
     // ------------------------------------------------------------------------
     // 0x04‑0x1D: Alphanumeric keys
     /// Keyboard a and A (Footnote 2)
-    Aa = KeyboardUsage::KeyboardAa as isize,
+    pub const Aa: KC = KC::new(KeyboardUsage::KeyboardAa as u16);
     /// Keyboard b and B
-    Bb = KeyboardUsage::KeyboardBb as isize,
+    pub const Bb: KC = KC::new(KeyboardUsage::KeyboardBb as u16);
     /// Keyboard c and C (Footnote 2)
-    Cc = KeyboardUsage::KeyboardCc as isize,
+    pub const Cc: KC = KC::new(KeyboardUsage::KeyboardCc as u16);
     /// Keyboard d and D
-    Dd = KeyboardUsage::KeyboardDd as isize,
+    pub const Dd: KC = KC::new(KeyboardUsage::KeyboardDd as u16);
     /// Keyboard e and E
-    Ee = KeyboardUsage::KeyboardEe as isize,
+    pub const Ee: KC = KC::new(KeyboardUsage::KeyboardEe as u16);
     /// Keyboard f and F
-    Ff = KeyboardUsage::KeyboardFf as isize,
+    pub const Ff: KC = KC::new(KeyboardUsage::KeyboardFf as u16);
     /// Keyboard g and G
-    Gg = KeyboardUsage::KeyboardGg as isize,
+    pub const Gg: KC = KC::new(KeyboardUsage::KeyboardGg as u16);
     /// Keyboard h and H
-    Hh = KeyboardUsage::KeyboardHh as isize,
+    pub const Hh: KC = KC::new(KeyboardUsage::KeyboardHh as u16);
     /// Keyboard i and I
-    Ii = KeyboardUsage::KeyboardIi as isize,
+    pub const Ii: KC = KC::new(KeyboardUsage::KeyboardIi as u16);
     /// Keyboard j and J
-    Jj = KeyboardUsage::KeyboardJj as isize,
+    pub const Jj: KC = KC::new(KeyboardUsage::KeyboardJj as u16);
     /// Keyboard k and K
-    Kk = KeyboardUsage::KeyboardKk as isize,
+    pub const Kk: KC = KC::new(KeyboardUsage::KeyboardKk as u16);
     /// Keyboard l and L
-    Ll = KeyboardUsage::KeyboardLl as isize,
+    pub const Ll: KC = KC::new(KeyboardUsage::KeyboardLl as u16);
     /// Keyboard m and M (Footnote 2)
-    Mm = KeyboardUsage::KeyboardMm as isize,
+    pub const Mm: KC = KC::new(KeyboardUsage::KeyboardMm as u16);
     /// Keyboard n and N
-    Nn = KeyboardUsage::KeyboardNn as isize,
+    pub const Nn: KC = KC::new(KeyboardUsage::KeyboardNn as u16);
     /// Keyboard o and O (Footnote 2)
-    Oo = KeyboardUsage::KeyboardOo as isize,
+    pub const Oo: KC = KC::new(KeyboardUsage::KeyboardOo as u16);
     /// Keyboard p and P (Footnote 2)
-    Pp = KeyboardUsage::KeyboardPp as isize,
+    pub const Pp: KC = KC::new(KeyboardUsage::KeyboardPp as u16);
     /// Keyboard q and Q (Footnote 2)
-    Qq = KeyboardUsage::KeyboardQq as isize,
+    pub const Qq: KC = KC::new(KeyboardUsage::KeyboardQq as u16);
     /// Keyboard r and R
-    Rr = KeyboardUsage::KeyboardRr as isize,
+    pub const Rr: KC = KC::new(KeyboardUsage::KeyboardRr as u16);
     /// Keyboard s and S
-    Ss = KeyboardUsage::KeyboardSs as isize,
+    pub const Ss: KC = KC::new(KeyboardUsage::KeyboardSs as u16);
     /// Keyboard t and T
-    Tt = KeyboardUsage::KeyboardTt as isize,
+    pub const Tt: KC = KC::new(KeyboardUsage::KeyboardTt as u16);
     /// Keyboard u and U
-    Uu = KeyboardUsage::KeyboardUu as isize,
+    pub const Uu: KC = KC::new(KeyboardUsage::KeyboardUu as u16);
     /// Keyboard v and V
-    Vv = KeyboardUsage::KeyboardVv as isize,
+    pub const Vv: KC = KC::new(KeyboardUsage::KeyboardVv as u16);
     /// Keyboard w and W (Footnote 2)
-    Ww = KeyboardUsage::KeyboardWw as isize,
+    pub const Ww: KC = KC::new(KeyboardUsage::KeyboardWw as u16);
     /// Keyboard x and X (Footnote 2)
-    Xx = KeyboardUsage::KeyboardXx as isize,
+    pub const Xx: KC = KC::new(KeyboardUsage::KeyboardXx as u16);
     /// Keyboard y and Y (Footnote 2)
-    Yy = KeyboardUsage::KeyboardYy as isize,
+    pub const Yy: KC = KC::new(KeyboardUsage::KeyboardYy as u16);
     /// Keyboard z and Z (Footnote 2)
-    Zz = KeyboardUsage::KeyboardZz as isize,
+    pub const Zz: KC = KC::new(KeyboardUsage::KeyboardZz as u16);
 
     // ------------------------------------------------------------------------
     // 0x1E‑0x27: Number row (with shifted symbols)
     /// Keyboard 1 and ! (Footnote 2)
-    K1 = KeyboardUsage::Keyboard1Exclamation as isize,
+    pub const K1: KC = KC::new(KeyboardUsage::Keyboard1Exclamation as u16);
     /// Keyboard 2 and @ (Footnote 2)
-    K2 = KeyboardUsage::Keyboard2At as isize,
+    pub const K2: KC = KC::new(KeyboardUsage::Keyboard2At as u16);
     /// Keyboard 3 and # (Footnote 2)
-    K3 = KeyboardUsage::Keyboard3Hash as isize,
+    pub const K3: KC = KC::new(KeyboardUsage::Keyboard3Hash as u16);
     /// Keyboard 4 and $ (Footnote 2)
-    K4 = KeyboardUsage::Keyboard4Dollar as isize,
+    pub const K4: KC = KC::new(KeyboardUsage::Keyboard4Dollar as u16);
     /// Keyboard 5 and % (Footnote 2)
-    K5 = KeyboardUsage::Keyboard5Percent as isize,
+    pub const K5: KC = KC::new(KeyboardUsage::Keyboard5Percent as u16);
     /// Keyboard 6 and ^ (Footnote 2)
-    K6 = KeyboardUsage::Keyboard6Caret as isize,
+    pub const K6: KC = KC::new(KeyboardUsage::Keyboard6Caret as u16);
     /// Keyboard 7 and & (Footnote 2)
-    K7 = KeyboardUsage::Keyboard7Ampersand as isize,
+    pub const K7: KC = KC::new(KeyboardUsage::Keyboard7Ampersand as u16);
     /// Keyboard 8 and * (Footnote 2)
-    K8 = KeyboardUsage::Keyboard8Asterisk as isize,
+    pub const K8: KC = KC::new(KeyboardUsage::Keyboard8Asterisk as u16);
     /// Keyboard 9 and ( (Footnote 2)
-    K9 = KeyboardUsage::Keyboard9OpenParens as isize,
+    pub const K9: KC = KC::new(KeyboardUsage::Keyboard9OpenParens as u16);
     /// Keyboard 0 and ) (Footnote 2)
-    K0 = KeyboardUsage::Keyboard0CloseParens as isize,
+    pub const K0: KC = KC::new(KeyboardUsage::Keyboard0CloseParens as u16);
 
     // ------------------------------------------------------------------------
     // 0x28‑0x2C: Basic control keys
     /// Keyboard Return (ENTER) (Footnote 3)
-    Enter = KeyboardUsage::KeyboardEnter as isize,
+    pub const Enter: KC = KC::new(KeyboardUsage::KeyboardEnter as u16);
     /// Keyboard ESCAPE
-    Escape = KeyboardUsage::KeyboardEscape as isize,
+    pub const Escape: KC = KC::new(KeyboardUsage::KeyboardEscape as u16);
     /// Keyboard DELETE (Backspace) (Footnote 4)
-    Backspace = KeyboardUsage::KeyboardBackspace as isize,
+    pub const Backspace: KC = KC::new(KeyboardUsage::KeyboardBackspace as u16);
     /// Keyboard Tab
-    Tab = KeyboardUsage::KeyboardTab as isize,
+    pub const Tab: KC = KC::new(KeyboardUsage::KeyboardTab as u16);
     /// Keyboard Spacebar
-    Space = KeyboardUsage::KeyboardSpacebar as isize,
+    pub const Space: KC = KC::new(KeyboardUsage::KeyboardSpacebar as u16);
 
     // ------------------------------------------------------------------------
     // 0x2D‑0x35: Symbol keys
     /// Keyboard - and _ (Footnote 2)
-    Dash = KeyboardUsage::KeyboardDashUnderscore as isize,
+    pub const Dash: KC = KC::new(KeyboardUsage::KeyboardDashUnderscore as u16);
     /// Keyboard = and + (Footnote 2)
-    Equal = KeyboardUsage::KeyboardEqualPlus as isize,
+    pub const Equal: KC = KC::new(KeyboardUsage::KeyboardEqualPlus as u16);
     /// Keyboard [ and { (Footnote 2)
-    OpenBracket = KeyboardUsage::KeyboardOpenBracketBrace as isize,
+    pub const OpenBracket: KC = KC::new(KeyboardUsage::KeyboardOpenBracketBrace as u16);
     /// Keyboard ] and } (Footnote 2)
-    CloseBracket = KeyboardUsage::KeyboardCloseBracketBrace as isize,
+    pub const CloseBracket: KC = KC::new(KeyboardUsage::KeyboardCloseBracketBrace as u16);
     /// Keyboard \ and |
-    Bslash = KeyboardUsage::KeyboardBackslashBar as isize,
+    pub const Bslash: KC = KC::new(KeyboardUsage::KeyboardBackslashBar as u16);
     /// Keyboard Non‑US # (Footnote 5)
-    NonUSHash = KeyboardUsage::KeyboardNonUSHash as isize,
+    pub const NonUSHash: KC = KC::new(KeyboardUsage::KeyboardNonUSHash as u16);
     /// Keyboard ; and : (Footnote 2)
-    SemiColon = KeyboardUsage::KeyboardSemiColon as isize,
+    pub const SemiColon: KC = KC::new(KeyboardUsage::KeyboardSemiColon as u16);
     /// Keyboard ' and " (Footnote 2)
-    Quote = KeyboardUsage::KeyboardSingleDoubleQuote as isize,
+    pub const Quote: KC = KC::new(KeyboardUsage::KeyboardSingleDoubleQuote as u16);
     /// Keyboard ` and ~ (Footnote 2)
-    BacktickTilde = KeyboardUsage::KeyboardBacktickTilde as isize,
+    pub const BacktickTilde: KC = KC::new(KeyboardUsage::KeyboardBacktickTilde as u16);
     /// Keyboard , and < (Footnote 2)
-    Comma = KeyboardUsage::KeyboardCommaLess as isize,
+    pub const Comma: KC = KC::new(KeyboardUsage::KeyboardCommaLess as u16);
     /// Keyboard . and > (Footnote 2)
-    Period = KeyboardUsage::KeyboardPeriodGreater as isize,
+    pub const Period: KC = KC::new(KeyboardUsage::KeyboardPeriodGreater as u16);
     /// Keyboard / and ? (Footnote 2)
-    Fslash = KeyboardUsage::KeyboardSlashQuestion as isize,
+    pub const Fslash: KC = KC::new(KeyboardUsage::KeyboardSlashQuestion as u16);
     /// Keyboard Caps Lock (Footnote 6)
-    CapsLock = KeyboardUsage::KeyboardCapsLock as isize,
+    pub const CapsLock: KC = KC::new(KeyboardUsage::KeyboardCapsLock as u16);
 
     // ------------------------------------------------------------------------
     // 0x3A‑0x45: Function keys
-    F1 = KeyboardUsage::KeyboardF1 as isize,
-    F2 = KeyboardUsage::KeyboardF2 as isize,
-    F3 = KeyboardUsage::KeyboardF3 as isize,
-    F4 = KeyboardUsage::KeyboardF4 as isize,
-    F5 = KeyboardUsage::KeyboardF5 as isize,
-    F6 = KeyboardUsage::KeyboardF6 as isize,
-    F7 = KeyboardUsage::KeyboardF7 as isize,
-    F8 = KeyboardUsage::KeyboardF8 as isize,
-    F9 = KeyboardUsage::KeyboardF9 as isize,
-    F10 = KeyboardUsage::KeyboardF10 as isize,
-    F11 = KeyboardUsage::KeyboardF11 as isize,
-    F12 = KeyboardUsage::KeyboardF12 as isize,
-    F13 = KeyboardUsage::KeyboardF13 as isize,
-    F14 = KeyboardUsage::KeyboardF14 as isize,
-    F15 = KeyboardUsage::KeyboardF15 as isize,
-    F16 = KeyboardUsage::KeyboardF16 as isize,
-    F17 = KeyboardUsage::KeyboardF17 as isize,
-    F18 = KeyboardUsage::KeyboardF18 as isize,
-    F19 = KeyboardUsage::KeyboardF19 as isize,
-    F20 = KeyboardUsage::KeyboardF20 as isize,
-    F21 = KeyboardUsage::KeyboardF21 as isize,
-    F22 = KeyboardUsage::KeyboardF22 as isize,
-    F23 = KeyboardUsage::KeyboardF23 as isize,
-    F24 = KeyboardUsage::KeyboardF24 as isize,
+    pub const F1: KC = KC::new(KeyboardUsage::KeyboardF1 as u16);
+    pub const F2: KC = KC::new(KeyboardUsage::KeyboardF2 as u16);
+    pub const F3: KC = KC::new(KeyboardUsage::KeyboardF3 as u16);
+    pub const F4: KC = KC::new(KeyboardUsage::KeyboardF4 as u16);
+    pub const F5: KC = KC::new(KeyboardUsage::KeyboardF5 as u16);
+    pub const F6: KC = KC::new(KeyboardUsage::KeyboardF6 as u16);
+    pub const F7: KC = KC::new(KeyboardUsage::KeyboardF7 as u16);
+    pub const F8: KC = KC::new(KeyboardUsage::KeyboardF8 as u16);
+    pub const F9: KC = KC::new(KeyboardUsage::KeyboardF9 as u16);
+    pub const F10: KC = KC::new(KeyboardUsage::KeyboardF10 as u16);
+    pub const F11: KC = KC::new(KeyboardUsage::KeyboardF11 as u16);
+    pub const F12: KC = KC::new(KeyboardUsage::KeyboardF12 as u16);
+    pub const F13: KC = KC::new(KeyboardUsage::KeyboardF13 as u16);
+    pub const F14: KC = KC::new(KeyboardUsage::KeyboardF14 as u16);
+    pub const F15: KC = KC::new(KeyboardUsage::KeyboardF15 as u16);
+    pub const F16: KC = KC::new(KeyboardUsage::KeyboardF16 as u16);
+    pub const F17: KC = KC::new(KeyboardUsage::KeyboardF17 as u16);
+    pub const F18: KC = KC::new(KeyboardUsage::KeyboardF18 as u16);
+    pub const F19: KC = KC::new(KeyboardUsage::KeyboardF19 as u16);
+    pub const F20: KC = KC::new(KeyboardUsage::KeyboardF20 as u16);
+    pub const F21: KC = KC::new(KeyboardUsage::KeyboardF21 as u16);
+    pub const F22: KC = KC::new(KeyboardUsage::KeyboardF22 as u16);
+    pub const F23: KC = KC::new(KeyboardUsage::KeyboardF23 as u16);
+    pub const F24: KC = KC::new(KeyboardUsage::KeyboardF24 as u16);
 
     // ------------------------------------------------------------------------
     // 0x46‑0x52: System / navigation keys
     /// Keyboard PrintScreen (Footnote 7)
-    PrintS = KeyboardUsage::KeyboardPrintScreen as isize,
+    pub const PrintS: KC = KC::new(KeyboardUsage::KeyboardPrintScreen as u16);
     /// Keyboard ScrollLock (Footnote 6)
-    ScrollLock = KeyboardUsage::KeyboardScrollLock as isize,
+    pub const ScrollLock: KC = KC::new(KeyboardUsage::KeyboardScrollLock as u16);
     /// Keyboard Pause (Footnote 7)
-    Pause = KeyboardUsage::KeyboardPause as isize,
+    pub const Pause: KC = KC::new(KeyboardUsage::KeyboardPause as u16);
     /// Keyboard Insert (Footnote 7)
-    Insert = KeyboardUsage::KeyboardInsert as isize,
+    pub const Insert: KC = KC::new(KeyboardUsage::KeyboardInsert as u16);
     /// Keyboard Home (Footnote 7)
-    Home = KeyboardUsage::KeyboardHome as isize,
+    pub const Home: KC = KC::new(KeyboardUsage::KeyboardHome as u16);
     /// Keyboard PageUp (Footnote 7)
-    PageUp = KeyboardUsage::KeyboardPageUp as isize,
+    pub const PageUp: KC = KC::new(KeyboardUsage::KeyboardPageUp as u16);
     /// Keyboard Delete Forward (Footnote 7, 8)
-    Delete = KeyboardUsage::KeyboardDelete as isize,
+    pub const Delete: KC = KC::new(KeyboardUsage::KeyboardDelete as u16);
     /// Keyboard End (Footnote 7)
-    End = KeyboardUsage::KeyboardEnd as isize,
+    pub const End: KC = KC::new(KeyboardUsage::KeyboardEnd as u16);
     /// Keyboard PageDown (Footnote 7)
-    PageDown = KeyboardUsage::KeyboardPageDown as isize,
+    pub const PageDown: KC = KC::new(KeyboardUsage::KeyboardPageDown as u16);
     /// Keyboard RightArrow (Footnote 7)
-    RightArr = KeyboardUsage::KeyboardRightArrow as isize,
+    pub const RightArr: KC = KC::new(KeyboardUsage::KeyboardRightArrow as u16);
     /// Keyboard LeftArrow (Footnote 7)
-    LeftArr = KeyboardUsage::KeyboardLeftArrow as isize,
+    pub const LeftArr: KC = KC::new(KeyboardUsage::KeyboardLeftArrow as u16);
     /// Keyboard DownArrow (Footnote 7)
-    DownArr = KeyboardUsage::KeyboardDownArrow as isize,
+    pub const DownArr: KC = KC::new(KeyboardUsage::KeyboardDownArrow as u16);
     /// Keyboard UpArrow (Footnote 7)
-    UpArr = KeyboardUsage::KeyboardUpArrow as isize,
+    pub const UpArr: KC = KC::new(KeyboardUsage::KeyboardUpArrow as u16);
 
     // ------------------------------------------------------------------------
     // 0x53‑0x58: Keypad basics
     /// Keypad Num Lock and Clear (Footnote 6)
-    NumLock = KeyboardUsage::KeypadNumLock as isize,
+    pub const NumLock: KC = KC::new(KeyboardUsage::KeypadNumLock as u16);
     /// Keypad / (Footnote 7)
-    KeypadDivide = KeyboardUsage::KeypadDivide as isize,
+    pub const KeypadDivide: KC = KC::new(KeyboardUsage::KeypadDivide as u16);
     /// Keypad *
-    KeypadMultiply = KeyboardUsage::KeypadMultiply as isize,
+    pub const KeypadMultiply: KC = KC::new(KeyboardUsage::KeypadMultiply as u16);
     /// Keypad -
-    KMinus = KeyboardUsage::KeypadMinus as isize,
+    pub const KMinus: KC = KC::new(KeyboardUsage::KeypadMinus as u16);
     /// Keypad +
-    KeypadPlus = KeyboardUsage::KeypadPlus as isize,
+    pub const KeypadPlus: KC = KC::new(KeyboardUsage::KeypadPlus as u16);
     /// Keypad ENTER (Footnote 3)
-    KeypadEnter = KeyboardUsage::KeypadEnter as isize,
+    pub const KeypadEnter: KC = KC::new(KeyboardUsage::KeypadEnter as u16);
 
     // ------------------------------------------------------------------------
     // 0x59‑0x63: Keypad extended keys
     /// Keypad 1 and End
-    Keypad1End = KeyboardUsage::Keypad1End as isize,
+    pub const Keypad1End: KC = KC::new(KeyboardUsage::Keypad1End as u16);
     /// Keypad 2 and DownArrow
-    Keypad2DownArrow = KeyboardUsage::Keypad2DownArrow as isize,
+    pub const Keypad2DownArrow: KC = KC::new(KeyboardUsage::Keypad2DownArrow as u16);
     /// Keypad 3 and PageDown
-    Keypad3PageDown = KeyboardUsage::Keypad3PageDown as isize,
+    pub const Keypad3PageDown: KC = KC::new(KeyboardUsage::Keypad3PageDown as u16);
     /// Keypad 4 and LeftArrow
-    Keypad4LeftArrow = KeyboardUsage::Keypad4LeftArrow as isize,
+    pub const Keypad4LeftArrow: KC = KC::new(KeyboardUsage::Keypad4LeftArrow as u16);
     /// Keypad 5
-    Keypad5 = KeyboardUsage::Keypad5 as isize,
+    pub const Keypad5: KC = KC::new(KeyboardUsage::Keypad5 as u16);
     /// Keypad 6 and RightArrow
-    Keypad6RightArrow = KeyboardUsage::Keypad6RightArrow as isize,
+    pub const Keypad6RightArrow: KC = KC::new(KeyboardUsage::Keypad6RightArrow as u16);
     /// Keypad 7 and Home
-    Keypad7Home = KeyboardUsage::Keypad7Home as isize,
+    pub const Keypad7Home: KC = KC::new(KeyboardUsage::Keypad7Home as u16);
     /// Keypad 8 and UpArrow
-    Keypad8UpArrow = KeyboardUsage::Keypad8UpArrow as isize,
+    pub const Keypad8UpArrow: KC = KC::new(KeyboardUsage::Keypad8UpArrow as u16);
     /// Keypad 9 and PageUp
-    Keypad9PageUp = KeyboardUsage::Keypad9PageUp as isize,
+    pub const Keypad9PageUp: KC = KC::new(KeyboardUsage::Keypad9PageUp as u16);
     /// Keypad 0 and Insert
-    Keypad0Insert = KeyboardUsage::Keypad0Insert as isize,
+    pub const Keypad0Insert: KC = KC::new(KeyboardUsage::Keypad0Insert as u16);
     /// Keypad . and Delete
-    KeypadPeriodDelete = KeyboardUsage::KeypadPeriodDelete as isize,
+    pub const KeypadPeriodDelete: KC = KC::new(KeyboardUsage::KeypadPeriodDelete as u16);
 
     // ------------------------------------------------------------------------
     // 0x64‑0x65: Miscellaneous keys
     /// Keyboard Non‑US \ and | (Footnote 9, 10)
-    USSlash = KeyboardUsage::KeyboardNonUSSlash as isize,
+    pub const USSlash: KC = KC::new(KeyboardUsage::KeyboardNonUSSlash as u16);
     /// Keyboard Application (Footnote 11)
-    Application = KeyboardUsage::KeyboardApplication as isize,
+    pub const Application: KC = KC::new(KeyboardUsage::KeyboardApplication as u16);
     /// Keyboard Power (Footnote 1)
-    Power = KeyboardUsage::KeyboardPower as isize,
+    pub const Power: KC = KC::new(KeyboardUsage::KeyboardPower as u16);
 
     // ------------------------------------------------------------------------
     // 0x66‑0x67: Keypad extra
     /// Keypad =
-    KeypadEqual = KeyboardUsage::KeypadEqual as isize,
+    pub const KeypadEqual: KC = KC::new(KeyboardUsage::KeypadEqual as u16);
 
     // ------------------------------------------------------------------------
     // 0x74‑0x7D: System control keys
-    Execute = KeyboardUsage::KeyboardExecute as isize,
-    Help = KeyboardUsage::KeyboardHelp as isize,
-    Menu = KeyboardUsage::KeyboardMenu as isize,
-    Select = KeyboardUsage::KeyboardSelect as isize,
-    Stop = KeyboardUsage::KeyboardStop as isize,
-    Again = KeyboardUsage::KeyboardAgain as isize,
-    Undo = KeyboardUsage::KeyboardUndo as isize,
-    Cut = KeyboardUsage::KeyboardCut as isize,
-    Copy = KeyboardUsage::KeyboardCopy as isize,
-    Paste = KeyboardUsage::KeyboardPaste as isize,
-    Find = KeyboardUsage::KeyboardFind as isize,
-    Mute = KeyboardUsage::KeyboardMute as isize,
-    VolumeUp = KeyboardUsage::KeyboardVolumeUp as isize,
-    VolumeDown = KeyboardUsage::KeyboardVolumeDown as isize,
+    pub const Execute: KC = KC::new(KeyboardUsage::KeyboardExecute as u16);
+    pub const Help: KC = KC::new(KeyboardUsage::KeyboardHelp as u16);
+    pub const Menu: KC = KC::new(KeyboardUsage::KeyboardMenu as u16);
+    pub const Select: KC = KC::new(KeyboardUsage::KeyboardSelect as u16);
+    pub const Stop: KC = KC::new(KeyboardUsage::KeyboardStop as u16);
+    pub const Again: KC = KC::new(KeyboardUsage::KeyboardAgain as u16);
+    pub const Undo: KC = KC::new(KeyboardUsage::KeyboardUndo as u16);
+    pub const Cut: KC = KC::new(KeyboardUsage::KeyboardCut as u16);
+    pub const Copy: KC = KC::new(KeyboardUsage::KeyboardCopy as u16);
+    pub const Paste: KC = KC::new(KeyboardUsage::KeyboardPaste as u16);
+    pub const Find: KC = KC::new(KeyboardUsage::KeyboardFind as u16);
+    pub const Mute: KC = KC::new(KeyboardUsage::KeyboardMute as u16);
+    pub const VolumeUp: KC = KC::new(KeyboardUsage::KeyboardVolumeUp as u16);
+    pub const VolumeDown: KC = KC::new(KeyboardUsage::KeyboardVolumeDown as u16);
 
     // ------------------------------------------------------------------------
     // 0x7E‑0x84: Locking keys
-    LockingCapsLock = KeyboardUsage::KeyboardLockingCapsLock as isize,
-    LockingNumLock = KeyboardUsage::KeyboardLockingNumLock as isize,
-    LockingScrollLock = KeyboardUsage::KeyboardLockingScrollLock as isize,
+    pub const LockingCapsLock: KC = KC::new(KeyboardUsage::KeyboardLockingCapsLock as u16);
+    pub const LockingNumLock: KC = KC::new(KeyboardUsage::KeyboardLockingNumLock as u16);
+    pub const LockingScrollLock: KC = KC::new(KeyboardUsage::KeyboardLockingScrollLock as u16);
 
     // ------------------------------------------------------------------------
     // 0x85‑0x86: Keypad punctuation
-    KeypadComma = KeyboardUsage::KeypadComma as isize,
-    KeypadEqualSign = KeyboardUsage::KeypadEqualSign as isize,
+    pub const KeypadComma: KC = KC::new(KeyboardUsage::KeypadComma as u16);
+    pub const KeypadEqualSign: KC = KC::new(KeyboardUsage::KeypadEqualSign as u16);
 
     // ------------------------------------------------------------------------
     // 0x87‑0x8F: International keys
-    International1 = KeyboardUsage::KeyboardInternational1 as isize,
-    International2 = KeyboardUsage::KeyboardInternational2 as isize,
-    International3 = KeyboardUsage::KeyboardInternational3 as isize,
-    International4 = KeyboardUsage::KeyboardInternational4 as isize,
-    International5 = KeyboardUsage::KeyboardInternational5 as isize,
-    International6 = KeyboardUsage::KeyboardInternational6 as isize,
-    International7 = KeyboardUsage::KeyboardInternational7 as isize,
-    International8 = KeyboardUsage::KeyboardInternational8 as isize,
-    International9 = KeyboardUsage::KeyboardInternational9 as isize,
+    pub const International1: KC = KC::new(KeyboardUsage::KeyboardInternational1 as u16);
+    pub const International2: KC = KC::new(KeyboardUsage::KeyboardInternational2 as u16);
+    pub const International3: KC = KC::new(KeyboardUsage::KeyboardInternational3 as u16);
+    pub const International4: KC = KC::new(KeyboardUsage::KeyboardInternational4 as u16);
+    pub const International5: KC = KC::new(KeyboardUsage::KeyboardInternational5 as u16);
+    pub const International6: KC = KC::new(KeyboardUsage::KeyboardInternational6 as u16);
+    pub const International7: KC = KC::new(KeyboardUsage::KeyboardInternational7 as u16);
+    pub const International8: KC = KC::new(KeyboardUsage::KeyboardInternational8 as u16);
+    pub const International9: KC = KC::new(KeyboardUsage::KeyboardInternational9 as u16);
 
     // ------------------------------------------------------------------------
     // 0x90‑0x98: Language keys
-    LANG1 = KeyboardUsage::KeyboardLANG1 as isize,
-    LANG2 = KeyboardUsage::KeyboardLANG2 as isize,
-    LANG3 = KeyboardUsage::KeyboardLANG3 as isize,
-    LANG4 = KeyboardUsage::KeyboardLANG4 as isize,
-    LANG5 = KeyboardUsage::KeyboardLANG5 as isize,
-    LANG6 = KeyboardUsage::KeyboardLANG6 as isize,
-    LANG7 = KeyboardUsage::KeyboardLANG7 as isize,
-    LANG8 = KeyboardUsage::KeyboardLANG8 as isize,
-    LANG9 = KeyboardUsage::KeyboardLANG9 as isize,
+    pub const LANG1: KC = KC::new(KeyboardUsage::KeyboardLANG1 as u16);
+    pub const LANG2: KC = KC::new(KeyboardUsage::KeyboardLANG2 as u16);
+    pub const LANG3: KC = KC::new(KeyboardUsage::KeyboardLANG3 as u16);
+    pub const LANG4: KC = KC::new(KeyboardUsage::KeyboardLANG4 as u16);
+    pub const LANG5: KC = KC::new(KeyboardUsage::KeyboardLANG5 as u16);
+    pub const LANG6: KC = KC::new(KeyboardUsage::KeyboardLANG6 as u16);
+    pub const LANG7: KC = KC::new(KeyboardUsage::KeyboardLANG7 as u16);
+    pub const LANG8: KC = KC::new(KeyboardUsage::KeyboardLANG8 as u16);
+    pub const LANG9: KC = KC::new(KeyboardUsage::KeyboardLANG9 as u16);
 
     // ------------------------------------------------------------------------
     // 0x99‑0x9C: Misc system keys
-    AlternateErase = KeyboardUsage::KeyboardAlternateErase as isize,
-    SysReqAttention = KeyboardUsage::KeyboardSysReqAttention as isize,
-    Cancel = KeyboardUsage::KeyboardCancel as isize,
-    Clear = KeyboardUsage::KeyboardClear as isize,
+    pub const AlternateErase: KC = KC::new(KeyboardUsage::KeyboardAlternateErase as u16);
+    pub const SysReqAttention: KC = KC::new(KeyboardUsage::KeyboardSysReqAttention as u16);
+    pub const Cancel: KC = KC::new(KeyboardUsage::KeyboardCancel as u16);
+    pub const Clear: KC = KC::new(KeyboardUsage::KeyboardClear as u16);
 
     // ------------------------------------------------------------------------
     // 0x9D‑0xA4: Navigation / selection keys
-    Prior = KeyboardUsage::KeyboardPrior as isize,
-    Return = KeyboardUsage::KeyboardReturn as isize,
-    Separator = KeyboardUsage::KeyboardSeparator as isize,
-    Out = KeyboardUsage::KeyboardOut as isize,
-    Oper = KeyboardUsage::KeyboardOper as isize,
-    ClearAgain = KeyboardUsage::KeyboardClearAgain as isize,
-    CrSelProps = KeyboardUsage::KeyboardCrSelProps as isize,
-    ExSel = KeyboardUsage::KeyboardExSel as isize,
+    pub const Prior: KC = KC::new(KeyboardUsage::KeyboardPrior as u16);
+    pub const Return: KC = KC::new(KeyboardUsage::KeyboardReturn as u16);
+    pub const Separator: KC = KC::new(KeyboardUsage::KeyboardSeparator as u16);
+    pub const Out: KC = KC::new(KeyboardUsage::KeyboardOut as u16);
+    pub const Oper: KC = KC::new(KeyboardUsage::KeyboardOper as u16);
+    pub const ClearAgain: KC = KC::new(KeyboardUsage::KeyboardClearAgain as u16);
+    pub const CrSelProps: KC = KC::new(KeyboardUsage::KeyboardCrSelProps as u16);
+    pub const ExSel: KC = KC::new(KeyboardUsage::KeyboardExSel as u16);
 
     // ------------------------------------------------------------------------
     // 0xB0‑0xBF: Keypad numeric extensions
-    Keypad00 = KeyboardUsage::Keypad00 as isize,
-    Keypad000 = KeyboardUsage::Keypad000 as isize,
-    ThousandsSeparator = KeyboardUsage::ThousandsSeparator as isize,
-    DecimalSeparator = KeyboardUsage::DecimalSeparator as isize,
-    CurrencyUnit = KeyboardUsage::CurrencyUnit as isize,
-    CurrencySubunit = KeyboardUsage::CurrencySubunit as isize,
-    OpenParens = KeyboardUsage::KeypadOpenParens as isize,
-    CloseParens = KeyboardUsage::KeypadCloseParens as isize,
-    OpenBrace = KeyboardUsage::KeypadOpenBrace as isize,
-    CloseBrace = KeyboardUsage::KeypadCloseBrace as isize,
-    KeypadTab = KeyboardUsage::KeypadTab as isize,
-    KeypadBackspace = KeyboardUsage::KeypadBackspace as isize,
-    A = KeyboardUsage::KeypadA as isize,
-    B = KeyboardUsage::KeypadB as isize,
-    C = KeyboardUsage::KeypadC as isize,
-    D = KeyboardUsage::KeypadD as isize,
+    pub const Keypad00: KC = KC::new(KeyboardUsage::Keypad00 as u16);
+    pub const Keypad000: KC = KC::new(KeyboardUsage::Keypad000 as u16);
+    pub const ThousandsSeparator: KC = KC::new(KeyboardUsage::ThousandsSeparator as u16);
+    pub const DecimalSeparator: KC = KC::new(KeyboardUsage::DecimalSeparator as u16);
+    pub const CurrencyUnit: KC = KC::new(KeyboardUsage::CurrencyUnit as u16);
+    pub const CurrencySubunit: KC = KC::new(KeyboardUsage::CurrencySubunit as u16);
+    pub const OpenParens: KC = KC::new(KeyboardUsage::KeypadOpenParens as u16);
+    pub const CloseParens: KC = KC::new(KeyboardUsage::KeypadCloseParens as u16);
+    pub const OpenBrace: KC = KC::new(KeyboardUsage::KeypadOpenBrace as u16);
+    pub const CloseBrace: KC = KC::new(KeyboardUsage::KeypadCloseBrace as u16);
+    pub const KeypadTab: KC = KC::new(KeyboardUsage::KeypadTab as u16);
+    pub const KeypadBackspace: KC = KC::new(KeyboardUsage::KeypadBackspace as u16);
+    pub const A: KC = KC::new(KeyboardUsage::KeypadA as u16);
+    pub const B: KC = KC::new(KeyboardUsage::KeypadB as u16);
+    pub const C: KC = KC::new(KeyboardUsage::KeypadC as u16);
+    pub const D: KC = KC::new(KeyboardUsage::KeypadD as u16);
 
     // ------------------------------------------------------------------------
     // 0xC0‑0xCA: Keypad logical / bitwise ops
-    E = KeyboardUsage::KeypadE as isize,
-    F = KeyboardUsage::KeypadF as isize,
-    BitwiseXor = KeyboardUsage::KeypadBitwiseXor as isize,
-    LogicalXor = KeyboardUsage::KeypadLogicalXor as isize,
-    Modulo = KeyboardUsage::KeypadModulo as isize,
-    LShift = KeyboardUsage::KeypadLeftShift as isize,
-    RightShift = KeyboardUsage::KeypadRightShift as isize,
-    BitwiseAnd = KeyboardUsage::KeypadBitwiseAnd as isize,
-    LogicalAnd = KeyboardUsage::KeypadLogicalAnd as isize,
-    BitwiseOr = KeyboardUsage::KeypadBitwiseOr as isize,
-    LogicalOr = KeyboardUsage::KeypadLogicalOr as isize,
-    Colon = KeyboardUsage::KeypadColon as isize,
-    Hash = KeyboardUsage::KeypadHash as isize,
-    KeypadSpace = KeyboardUsage::KeypadSpace as isize,
-    At = KeyboardUsage::KeypadAt as isize,
-    Exclamation = KeyboardUsage::KeypadExclamation as isize,
+    pub const E: KC = KC::new(KeyboardUsage::KeypadE as u16);
+    pub const F: KC = KC::new(KeyboardUsage::KeypadF as u16);
+    pub const BitwiseXor: KC = KC::new(KeyboardUsage::KeypadBitwiseXor as u16);
+    pub const LogicalXor: KC = KC::new(KeyboardUsage::KeypadLogicalXor as u16);
+    pub const Modulo: KC = KC::new(KeyboardUsage::KeypadModulo as u16);
+    pub const LShift: KC = KC::new(KeyboardUsage::KeypadLeftShift as u16);
+    pub const RightShift: KC = KC::new(KeyboardUsage::KeypadRightShift as u16);
+    pub const BitwiseAnd: KC = KC::new(KeyboardUsage::KeypadBitwiseAnd as u16);
+    pub const LogicalAnd: KC = KC::new(KeyboardUsage::KeypadLogicalAnd as u16);
+    pub const BitwiseOr: KC = KC::new(KeyboardUsage::KeypadBitwiseOr as u16);
+    pub const LogicalOr: KC = KC::new(KeyboardUsage::KeypadLogicalOr as u16);
+    pub const Colon: KC = KC::new(KeyboardUsage::KeypadColon as u16);
+    pub const Hash: KC = KC::new(KeyboardUsage::KeypadHash as u16);
+    pub const KeypadSpace: KC = KC::new(KeyboardUsage::KeypadSpace as u16);
+    pub const At: KC = KC::new(KeyboardUsage::KeypadAt as u16);
+    pub const Exclamation: KC = KC::new(KeyboardUsage::KeypadExclamation as u16);
 
     // ------------------------------------------------------------------------
     // 0xD0‑0xD9: Keypad memory functions
-    MemoryStore = KeyboardUsage::KeypadMemoryStore as isize,
-    MemoryRecall = KeyboardUsage::KeypadMemoryRecall as isize,
-    MemoryClear = KeyboardUsage::KeypadMemoryClear as isize,
-    MemoryAdd = KeyboardUsage::KeypadMemoryAdd as isize,
-
-    MemorySubtract = KeyboardUsage::KeypadMemorySubtract as isize,
-    MemoryMultiply = KeyboardUsage::KeypadMemoryMultiply as isize,
-    MemoryDivide = KeyboardUsage::KeypadMemoryDivide as isize,
-    PositiveNegative = KeyboardUsage::KeypadPositiveNegative as isize,
-    KeypadClear = KeyboardUsage::KeypadClear as isize,
-    ClearEntry = KeyboardUsage::KeypadClearEntry as isize,
-    Binary = KeyboardUsage::KeypadBinary as isize,
-    Octal = KeyboardUsage::KeypadOctal as isize,
-    Decimal = KeyboardUsage::KeypadDecimal as isize,
-    Hexadecimal = KeyboardUsage::KeypadHexadecimal as isize,
+    pub const MemoryStore: KC = KC::new(KeyboardUsage::KeypadMemoryStore as u16);
+    pub const MemoryRecall: KC = KC::new(KeyboardUsage::KeypadMemoryRecall as u16);
+    pub const MemoryClear: KC = KC::new(KeyboardUsage::KeypadMemoryClear as u16);
+    pub const MemoryAdd: KC = KC::new(KeyboardUsage::KeypadMemoryAdd as u16);
+
+    pub const MemorySubtract: KC = KC::new(KeyboardUsage::KeypadMemorySubtract as u16);
+    pub const MemoryMultiply: KC = KC::new(KeyboardUsage::KeypadMemoryMultiply as u16);
+    pub const MemoryDivide: KC = KC::new(KeyboardUsage::KeypadMemoryDivide as u16);
+    pub const PositiveNegative: KC = KC::new(KeyboardUsage::KeypadPositiveNegative as u16);
+    pub const KeypadClear: KC = KC::new(KeyboardUsage::KeypadClear as u16);
+    pub const ClearEntry: KC = KC::new(KeyboardUsage::KeypadClearEntry as u16);
+    pub const Binary: KC = KC::new(KeyboardUsage::KeypadBinary as u16);
+    pub const Octal: KC = KC::new(KeyboardUsage::KeypadOctal as u16);
+    pub const Decimal: KC = KC::new(KeyboardUsage::KeypadDecimal as u16);
+    pub const Hexadecimal: KC = KC::new(KeyboardUsage::KeypadHexadecimal as u16);
 
     // ------------------------------------------------------------------------
     // 0xE0‑0xE7: Modifier keys
-    LCtrl = KeyboardUsage::KeyboardLeftControl as isize,
-    LeftShift = KeyboardUsage::KeyboardLeftShift as isize,
-    LAlt = KeyboardUsage::KeyboardLeftAlt as isize,
-    LGUI = KeyboardUsage::KeyboardLeftGUI as isize,
-    RCtrs = KeyboardUsage::KeyboardRightControl as isize,
-    RShift = KeyboardUsage::KeyboardRightShift as isize,
-    RAlt = KeyboardUsage::KeyboardRightAlt as isize,
-    RGUI = KeyboardUsage::KeyboardRightGUI as isize,
+    pub const LCtrl: KC = KC::new(KeyboardUsage::KeyboardLeftControl as u16);
+    pub const LeftShift: KC = KC::new(KeyboardUsage::KeyboardLeftShift as u16);
+    pub const LAlt: KC = KC::new(KeyboardUsage::KeyboardLeftAlt as u16);
+    pub const LGUI: KC = KC::new(KeyboardUsage::KeyboardLeftGUI as u16);
+    pub const RCtrs: KC = KC::new(KeyboardUsage::KeyboardRightControl as u16);
+    pub const RShift: KC = KC::new(KeyboardUsage::KeyboardRightShift as u16);
+    pub const RAlt: KC = KC::new(KeyboardUsage::KeyboardRightAlt as u16);
+    pub const RGUI: KC = KC::new(KeyboardUsage::KeyboardRightGUI as u16);
 
     // ------------------------------------------------------------------------
     // 0xE8‑0xFF: Reserved / invalid values
-    Reserved = KeyboardUsage::Reserved as isize,
+    pub const Reserved: KC = KC::new(KeyboardUsage::Reserved as u16);
 
     // -----------------------------------------------------------------------
     // Custom Internal Keycodes
-    /// Layer 1
-    L1 = 0xF0,
-    /// Layer 2
-    L2 = 0xF1,
-    /// Layer 3
-    L3 = 0xF2,
-    /// Layer 4
-    L4 = 0xF3,
-    /// Layer 5
-    L5 = 0xF4,
+    /// Layer 1, active while held
+    pub const L1: KC = KC::new(0xF0);
+    /// Layer 2, active while held
+    pub const L2: KC = KC::new(0xF1);
+    /// Layer 3, active while held
+    pub const L3: KC = KC::new(0xF2);
+    /// Layer 4, active while held
+    pub const L4: KC = KC::new(0xF3);
+    /// Layer 5, active while held
+    pub const L5: KC = KC::new(0xF4);
+
+    /// Toggle layer 1
+    pub const TG1: KC = KC::new(0x110);
+    /// Toggle layer 2
+    pub const TG2: KC = KC::new(0x111);
+    /// Toggle layer 3
+    pub const TG3: KC = KC::new(0x112);
+    /// Toggle layer 4
+    pub const TG4: KC = KC::new(0x113);
+    /// Toggle layer 5
+    pub const TG5: KC = KC::new(0x114);
+
+    /// Turn off all layers except the default one
+    pub const TO0: KC = KC::new(0x118);
+    /// Turn on layer 1, turn off all other layers
+    pub const TO1: KC = KC::new(0x119);
+    /// Turn on layer 2, turn off all other layers
+    pub const TO2: KC = KC::new(0x11A);
+    /// Turn on layer 3, turn off all other layers
+    pub const TO3: KC = KC::new(0x11B);
+    /// Turn on layer 4, turn off all other layers
+    pub const TO4: KC = KC::new(0x11C);
+    /// Turn on layer 5, turn off all other layers
+    pub const TO5: KC = KC::new(0x11D);
+
+    /// Set layer 0 as default layer
+    pub const DF0: KC = KC::new(0x120);
+    /// Set layer 1 as default layer
+    pub const DF1: KC = KC::new(0x121);
+    /// Set layer 2 as default layer
+    pub const DF2: KC = KC::new(0x122);
+    /// Set layer 3 as default layer
+    pub const DF3: KC = KC::new(0x123);
+    /// Set layer 4 as default layer
+    pub const DF4: KC = KC::new(0x124);
+    /// Set layer 5 as default layer
+    pub const DF5: KC = KC::new(0x125);
+
+    /// Layer 1 for the next key press only
+    pub const OSL1: KC = KC::new(0x128);
+    /// Layer 2 for the next key press only
+    pub const OSL2: KC = KC::new(0x129);
+    /// Layer 3 for the next key press only
+    pub const OSL3: KC = KC::new(0x12A);
+    /// Layer 4 for the next key press only
+    pub const OSL4: KC = KC::new(0x12B);
+    /// Layer 5 for the next key press only
+    pub const OSL5: KC = KC::new(0x12C);
+
+    /// Keep the held momentary layers on after their keys are released, until pressed again.
+    /// Bound on the layer it locks, e.g. the space thumb of layer 1 in the default keymaps
+    pub const LayerLock: KC = KC::new(0x12D);
+
+    /// Left Control for the next key press only
+    pub const OSMLCtrl: KC = KC::new(0x138);
+    /// Left Shift for the next key press only
+    pub const OSMLShift: KC = KC::new(0x139);
+    /// Left Alt for the next key press only
+    pub const OSMLAlt: KC = KC::new(0x13A);
+    /// Left GUI for the next key press only
+    pub const OSMLGUI: KC = KC::new(0x13B);
 
     // Enter Bootloader
-    BTL = 0xF5,
+    pub const BTL: KC = KC::new(0xF5);
+
+    /// Transparent, uses the key of the next active layer below
+    pub const Trans: KC = KC::new(0xF6);
+    /// No operation, the key does nothing
+    pub const NoOp: KC = KC::new(0xF7);
+
+    // -----------------------------------------------------------------------
+    // Tap-hold keys, configured in `config::TAP_HOLD_KEYS`
+    /// Tap-hold 1
+    pub const TH1: KC = KC::new(0x100);
+    /// Tap-hold 2
+    pub const TH2: KC = KC::new(0x101);
+    /// Tap-hold 3
+    pub const TH3: KC = KC::new(0x102);
+    /// Tap-hold 4
+    pub const TH4: KC = KC::new(0x103);
+    /// Tap-hold 5
+    pub const TH5: KC = KC::new(0x104);
+    /// Tap-hold 6
+    pub const TH6: KC = KC::new(0x105);
+    /// Tap-hold 7
+    pub const TH7: KC = KC::new(0x106);
+    /// Tap-hold 8
+    pub const TH8: KC = KC::new(0x107);
+
+    // -----------------------------------------------------------------------
+    // Macro keys, configured in `config::MACROS`
+    /// Macro 1
+    pub const MA1: KC = KC::new(0x130);
+    /// Macro 2
+    pub const MA2: KC = KC::new(0x131);
+    /// Macro 3
+    pub const MA3: KC = KC::new(0x132);
+    /// Macro 4
+    pub const MA4: KC = KC::new(0x133);
+    /// Macro 5
+    pub const MA5: KC = KC::new(0x134);
+    /// Macro 6
+    pub const MA6: KC = KC::new(0x135);
+    /// Macro 7
+    pub const MA7: KC = KC::new(0x136);
+    /// Macro 8
+    pub const MA8: KC = KC::new(0x137);
+
+    // -----------------------------------------------------------------------
+    // Tap-dance keys, configured in `config::TAP_DANCE_KEYS`
+    /// Tap-dance 1
+    pub const TD1: KC = KC::new(0x140);
+    /// Tap-dance 2
+    pub const TD2: KC = KC::new(0x141);
+    /// Tap-dance 3
+    pub const TD3: KC = KC::new(0x142);
+    /// Tap-dance 4
+    pub const TD4: KC = KC::new(0x143);
+    /// Tap-dance 5
+    pub const TD5: KC = KC::new(0x144);
+    /// Tap-dance 6
+    pub const TD6: KC = KC::new(0x145);
+    /// Tap-dance 7
+    pub const TD7: KC = KC::new(0x146);
+    /// Tap-dance 8
+    pub const TD8: KC = KC::new(0x147);
+
+    // -----------------------------------------------------------------------
+    // Mouse keys
+    /// Mouse move left
+    pub const MoGL: KC = KC::new(0x150);
+    /// Mouse move down
+    pub const MoGD: KC = KC::new(0x151);
+    /// Mouse move up
+    pub const MoGU: KC = KC::new(0x152);
+    /// Mouse move right
+    pub const MoGR: KC = KC::new(0x153);
+    /// Mouse left click
+    pub const MoLC: KC = KC::new(0x154);
+    /// Mouse right click
+    pub const MoRC: KC = KC::new(0x155);
+    /// Mouse middle click
+    pub const MoMC: KC = KC::new(0x156);
+    /// Mouse scroll left
+    pub const MoSL: KC = KC::new(0x157);
+    /// Mouse scroll right
+    pub const MoSR: KC = KC::new(0x158);
+    /// Mouse scroll up
+    pub const MoSU: KC = KC::new(0x159);
+    /// Mouse scroll down
+    pub const MoSD: KC = KC::new(0x15A);
+    /// Mouse fast speed while held
+    pub const MoCF: KC = KC::new(0x15B);
+    /// Mouse normal speed while held
+    pub const MoCN: KC = KC::new(0x15C);
+    /// Mouse slow speed while held
+    pub const MoCS: KC = KC::new(0x15D);
+
+    // -----------------------------------------------------------------------
+    // Media keys, sent in the consumer control report
+    /// Media mute
+    pub const MeMute: KC = KC::new(0x160);
+    /// Media volume up
+    pub const MeVolUp: KC = KC::new(0x161);
+    /// Media volume down
+    pub const MeVolDown: KC = KC::new(0x162);
+    /// Media play / pause
+    pub const MePlay: KC = KC::new(0x163);
+    /// Media stop
+    pub const MeStop: KC = KC::new(0x164);
+    /// Media next track
+    pub const MeNext: KC = KC::new(0x165);
+    /// Media previous track
+    pub const MePrev: KC = KC::new(0x166);
+    /// Media screen brightness up
+    pub const MeBriUp: KC = KC::new(0x167);
+    /// Media screen brightness down
+    pub const MeBriDown: KC = KC::new(0x168);
+
+    // -----------------------------------------------------------------------
+    // System keys, sent in the system control report
+    /// System power down
+    pub const SyPower: KC = KC::new(0x170);
+    /// System sleep
+    pub const SySleep: KC = KC::new(0x171);
+    /// System wake up
+    pub const SyWake: KC = KC::new(0x172);
+
+    // -----------------------------------------------------------------------
+    // Keyboard report mode
+    /// Switch to n-key rollover
+    pub const NkOn: KC = KC::new(0x178);
+    /// Switch to 6-key rollover
+    pub const NkOff: KC = KC::new(0x179);
+    /// Toggle between n-key and 6-key rollover
+    pub const NkTog: KC = KC::new(0x17A);
+
+    // -----------------------------------------------------------------------
+    // Auto-shift
+    /// Toggle auto-shift, letters and digits held past `config::AUTO_SHIFT_TERM` are shifted
+    pub const AsTog: KC = KC::new(0x17C);
+
+    // -----------------------------------------------------------------------
+    // Caps word
+    /// Shift the letters until a key other than a letter, digit, `-`, backspace or delete is pressed,
+    /// `-` is shifted to `_`
+    pub const CapsWord: KC = KC::new(0x180);
+
+    // -----------------------------------------------------------------------
+    // Leader key, the sequences are configured in `config::LEADER_SEQUENCES`
+    /// Start a leader sequence, the next keys are not sent but matched against the sequences
+    pub const Leader: KC = KC::new(0x184);
+
+    // -----------------------------------------------------------------------
+    // Repeat keys
+    /// Send the last key again, with the modifiers that were active
+    pub const Repeat: KC = KC::new(0x185);
+    /// Send the alternate of the last key (e.g. right after left), configured in `config::ALT_REPEAT_KEYS`
+    pub const AltRepeat: KC = KC::new(0x186);
+
+    // -----------------------------------------------------------------------
+    // Compound modifiers
+    /// Left Control + Left Shift + Left Alt
+    pub const Meh: KC = KC::new(0x188);
+    /// Left Control + Left Shift + Left Alt + Left GUI
+    pub const Hyper: KC = KC::new(0x189);
+}
+
+impl Default for KC {
+    fn default() -> Self {
+        KC::NoOp
+    }
+}
+
+/// Left Control + key
+#[allow(non_snake_case)]
+pub const fn LC(kc: KC) -> KC {
+    kc.with_mods(0x01)
+}
+
+/// Left Shift + key, e.g. `LS(KC::K9)` for `(`
+#[allow(non_snake_case)]
+pub const fn LS(kc: KC) -> KC {
+    kc.with_mods(0x02)
+}
+
+/// Left Alt + key
+#[allow(non_snake_case)]
+pub const fn LA(kc: KC) -> KC {
+    kc.with_mods(0x04)
+}
+
+/// Left GUI + key
+#[allow(non_snake_case)]
+pub const fn LG(kc: KC) -> KC {
+    kc.with_mods(0x08)
 }
 
 impl KC {
+    const fn new(code: u16) -> Self {
+        Self { code, mods: 0 }
+    }
+
+    /// Add modifiers that are pressed along with the key, the wrappers can be nested (`LC(LS(KC::Tab))`)
+    pub const fn with_mods(self, mods: u8) -> Self {
+        Self {
+            code: self.code,
+            mods: self.mods | mods,
+        }
+    }
+
+    /// Get the code of the key, the HID usage of the keyboard keys
+    pub const fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Get the modifiers pressed along with the key
+    pub const fn mods(&self) -> u8 {
+        self.mods
+    }
+
+    /// Get the key without its modifiers
+    pub const fn without_mods(self) -> Self {
+        Self::new(self.code)
+    }
+
     pub fn get_modifier(&self) -> u8 {
-        match self {
-            KC::LCtrl => 0x01,
-            KC::LShift => 0x02,
-            KC::LAlt => 0x04,
-            KC::LGUI => 0x08,
-            // KC::RShift => {}
-            // KC::RCtrs => {}
-            // KC::RAlt => {}
-            // KC::RGUI => {}
+        match *self {
+            KC::LCtrl | KC::OSMLCtrl => 0x01,
+            KC::LShift | KC::OSMLShift => 0x02,
+            KC::LAlt | KC::OSMLAlt => 0x04,
+            KC::LGUI | KC::OSMLGUI => 0x08,
+            KC::RCtrs => 0x10,
+            KC::RShift => 0x20,
+            KC::RAlt => 0x40,
+            KC::RGUI => 0x80,
+            // ctrl + shift + alt (+ gui)
+            KC::Meh => 0x07,
+            KC::Hyper => 0x0F,
+            _ => 0x00,
+        }
+    }
+
+    pub fn get_mouse_button(&self) -> u8 {
+        match *self {
+            KC::MoLC => 0x01,
+            KC::MoRC => 0x02,
+            KC::MoMC => 0x04,
+            _ => 0x00,
+        }
+    }
+
+    /// Get the usage id of a media key on the consumer page
+    pub fn get_consumer_usage(&self) -> u16 {
+        match *self {
+            KC::MeMute => 0xE2,
+            KC::MeVolUp => 0xE9,
+            KC::MeVolDown => 0xEA,
+            KC::MePlay => 0xCD,
+            KC::MeStop => 0xB7,
+            KC::MeNext => 0xB5,
+            KC::MePrev => 0xB6,
+            KC::MeBriUp => 0x6F,
+            KC::MeBriDown => 0x70,
+            _ => 0x00,
+        }
+    }
+
+    /// Get the usage id of a system key on the generic desktop page
+    pub fn get_system_usage(&self) -> u8 {
+        match *self {
+            KC::SyPower => 0x81,
+            KC::SySleep => 0x82,
+            KC::SyWake => 0x83,
             _ => 0x00,
         }
     }
 
     pub fn get_layer(&self) -> u8 {
-        match self {
-            KC::L1 => 1,
-            KC::L2 => 2,
-            KC::L3 => 3,
-            KC::L4 => 4,
-            KC::L5 => 5,
+        match *self {
+            KC::L1 | KC::TG1 | KC::TO1 | KC::DF1 | KC::OSL1 => 1,
+            KC::L2 | KC::TG2 | KC::TO2 | KC::DF2 | KC::OSL2 => 2,
+            KC::L3 | KC::TG3 | KC::TO3 | KC::DF3 | KC::OSL3 => 3,
+            KC::L4 | KC::TG4 | KC::TO4 | KC::DF4 | KC::OSL4 => 4,
+            KC::L5 | KC::TG5 | KC::TO5 | KC::DF5 | KC::OSL5 => 5,
             _ => 0,
         }
     }
+
+    /// Get the entry of a tap-hold key in the `config::TAP_HOLD_KEYS` table
+    pub fn get_tap_hold(&self, tap_hold_keys: &'static [TapHold]) -> Option<&'static TapHold> {
+        let index = match *self {
+            KC::TH1 => 0,
+            KC::TH2 => 1,
+            KC::TH3 => 2,
+            KC::TH4 => 3,
+            KC::TH5 => 4,
+            KC::TH6 => 5,
+            KC::TH7 => 6,
+            KC::TH8 => 7,
+            _ => return None,
+        };
+
+        tap_hold_keys.get(index)
+    }
+
+    /// Get the steps of a macro key in the `config::MACROS` table
+    pub fn get_macro(
+        &self,
+        macros: &'static [&'static [MacroStep]],
+    ) -> Option<&'static [MacroStep]> {
+        let index = match *self {
+            KC::MA1 => 0,
+            KC::MA2 => 1,
+            KC::MA3 => 2,
+            KC::MA4 => 3,
+            KC::MA5 => 4,
+            KC::MA6 => 5,
+            KC::MA7 => 6,
+            KC::MA8 => 7,
+            _ => return None,
+        };
+
+        macros.get(index).copied()
+    }
+
+    /// Get the entry of a tap-dance key in the `config::TAP_DANCE_KEYS` table
+    pub fn get_tap_dance(&self, tap_dance_keys: &'static [TapDance]) -> Option<&'static TapDance> {
+        let index = match *self {
+            KC::TD1 => 0,
+            KC::TD2 => 1,
+            KC::TD3 => 2,
+            KC::TD4 => 3,
+            KC::TD5 => 4,
+            KC::TD6 => 5,
+            KC::TD7 => 6,
+            KC::TD8 => 7,
+            _ => return None,
+        };
+
+        tap_dance_keys.get(index)
+    }
+
+    /// Check if a key continues a caps word and if it is shifted (the letters and `-`),
+    /// `None` for the keys that end it. Modified keys count as their key (e.g. `LS(KC::Dash)`)
+    pub fn caps_word_shift(&self) -> Option<bool> {
+        let usage = self.code;
+
+        match self.without_mods() {
+            KC::Dash => Some(true),
+            KC::Backspace | KC::Delete => Some(false),
+            _ if (KC::Aa.code..=KC::Zz.code).contains(&usage) => Some(true),
+            _ if (KC::K1.code..=KC::K0.code).contains(&usage) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Decides when a pressed tap-hold key turns into a hold
+#[cfg_attr(feature = "defmt", derive(Format))]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HoldFlavor {
+    /// Hold as soon as another key is pressed while the tap-hold key is down
+    HoldPreferred,
+    /// Hold when another key is pressed and released while the tap-hold key is down
+    Balanced,
+    /// Hold only when the tapping term expires
+    TapPreferred,
+}
+
+/// Key that sends `tap` when tapped and `hold` when held (e.g. home row mods).
+/// With a layer key as `hold` it is a layer-tap key
+#[derive(Debug, Clone, Copy)]
+pub struct TapHold {
+    pub tap: KC,
+    pub hold: KC,
+    pub flavor: HoldFlavor,
+    /// The key is a hold once it is pressed for longer than this
+    pub tapping_term: Duration,
+}
+
+/// Key with a different output for 1, 2 or 3 taps, a hold or a tap followed by a hold
+#[derive(Debug, Clone, Copy)]
+pub struct TapDance {
+    /// Output of 1, 2 and 3 taps
+    pub taps: [KC; 3],
+    /// Output when the key is held on the first press
+    pub hold: KC,
+    /// Output when the key is tapped once and then held
+    pub tap_hold: KC,
+    /// The dance ends when the key is not pressed again within this period
+    pub tapping_term: Duration,
+}
+
+impl TapDance {
+    /// Get the output after a number of taps, with the key still held or not
+    pub fn output(&self, taps: u8, held: bool) -> KC {
+        match (taps, held) {
+            (1, true) if self.hold != KC::NoOp => self.hold,
+            (2, true) if self.tap_hold != KC::NoOp => self.tap_hold,
+            _ => self.taps[taps.clamp(1, 3) as usize - 1],
+        }
+    }
 }
 
 pub enum KeyType {
@@ -438,33 +881,57 @@ pub enum KeyType {
     Macro,
     Modifier,
     Mouse,
+    Consumer,
+    SystemControl,
+    ReportMode,
+    AutoShift,
+    CapsWord,
+    Leader,
+    Repeat,
     Key,
     Layer,
+    LayerToggle,
+    LayerTo,
+    LayerDefault,
+    LayerOneShot,
+    LayerLock,
+    ModifierOneShot,
+    TapHold,
+    TapDance,
+    NoOp,
 }
 
 impl KeyType {
     pub fn check_type(key: &KC) -> KeyType {
         match *key {
-            // // return Macro key type
-            // KC::MaLP
-            // | KC::MaRP
-            // | KC::MaCp
-            // | KC::MaPa
-            // | KC::MaEx
-            // | KC::MaAt
-            // | KC::MaHs
-            // | KC::MaDl
-            // | KC::MaMd
-            // | KC::MaCa
-            // | KC::MaAmp
-            // | KC::MaAst
-            // | KC::MaSL
-            // | KC::MaLB
-            // | KC::MaRB
-            // | KC::MaPipe => KeyType::Macro,
+            // return Macro key type
+            KC::MA1 | KC::MA2 | KC::MA3 | KC::MA4 | KC::MA5 | KC::MA6 | KC::MA7 | KC::MA8 => {
+                KeyType::Macro
+            }
+
+            // return TapHold key type
+            KC::TH1 | KC::TH2 | KC::TH3 | KC::TH4 | KC::TH5 | KC::TH6 | KC::TH7 | KC::TH8 => {
+                KeyType::TapHold
+            }
+
+            // return TapDance key type
+            KC::TD1 | KC::TD2 | KC::TD3 | KC::TD4 | KC::TD5 | KC::TD6 | KC::TD7 | KC::TD8 => {
+                KeyType::TapDance
+            }
+
+            // return NoOp key type
+            KC::NoOp | KC::Trans => KeyType::NoOp,
 
             // return Layer key type
             KC::L1 | KC::L2 | KC::L3 | KC::L4 | KC::L5 => KeyType::Layer,
+            KC::TG1 | KC::TG2 | KC::TG3 | KC::TG4 | KC::TG5 => KeyType::LayerToggle,
+            KC::TO0 | KC::TO1 | KC::TO2 | KC::TO3 | KC::TO4 | KC::TO5 => KeyType::LayerTo,
+            KC::DF0 | KC::DF1 | KC::DF2 | KC::DF3 | KC::DF4 | KC::DF5 => KeyType::LayerDefault,
+            KC::OSL1 | KC::OSL2 | KC::OSL3 | KC::OSL4 | KC::OSL5 => KeyType::LayerOneShot,
+            KC::LayerLock => KeyType::LayerLock,
+
+            // return one-shot Modifier key type
+            KC::OSMLCtrl | KC::OSMLShift | KC::OSMLAlt | KC::OSMLGUI => KeyType::ModifierOneShot,
 
             // return Modifier key type
             KC::LShift
@@ -474,22 +941,54 @@ impl KeyType {
             | KC::RShift
             | KC::RCtrs
             | KC::RAlt
-            | KC::RGUI => KeyType::Modifier,
-
-            // // return Mouse key type
-            // KC::MoGL
-            // | KC::MoGD
-            // | KC::MoGU
-            // | KC::MoGR
-            // | KC::MoLC
-            // | KC::MoRC
-            // | KC::MoSL
-            // | KC::MoSR
-            // | KC::MoSU
-            // | KC::MoSD
-            // | KC::MoCF
-            // | KC::MoCN
-            // | KC::MoCS => KeyType::Mouse,
+            | KC::RGUI
+            | KC::Meh
+            | KC::Hyper => KeyType::Modifier,
+
+            // return Mouse key type
+            KC::MoGL
+            | KC::MoGD
+            | KC::MoGU
+            | KC::MoGR
+            | KC::MoLC
+            | KC::MoRC
+            | KC::MoMC
+            | KC::MoSL
+            | KC::MoSR
+            | KC::MoSU
+            | KC::MoSD
+            | KC::MoCF
+            | KC::MoCN
+            | KC::MoCS => KeyType::Mouse,
+
+            // return Consumer key type
+            KC::MeMute
+            | KC::MeVolUp
+            | KC::MeVolDown
+            | KC::MePlay
+            | KC::MeStop
+            | KC::MeNext
+            | KC::MePrev
+            | KC::MeBriUp
+            | KC::MeBriDown => KeyType::Consumer,
+
+            // return SystemControl key type
+            KC::SyPower | KC::SySleep | KC::SyWake => KeyType::SystemControl,
+
+            // return ReportMode key type
+            KC::NkOn | KC::NkOff | KC::NkTog => KeyType::ReportMode,
+
+            // return AutoShift key type
+            KC::AsTog => KeyType::AutoShift,
+
+            // return CapsWord key type
+            KC::CapsWord => KeyType::CapsWord,
+
+            // return Leader key type
+            KC::Leader => KeyType::Leader,
+
+            // return Repeat key type
+            KC::Repeat | KC::AltRepeat => KeyType::Repeat,
 
             // return Combo key type
             // KC::ComboCtrlD => KeyType::Combo,
@@ -0,0 +1,7 @@
+/// Layer that is active while all the `if_layers` are active, e.g. layers 1 and 2 => layer 3 (tri-layer)
+#[derive(Debug, Clone, Copy)]
+pub struct LayerCondition {
+    /// Bitmask of the layers that have to be active
+    pub if_layers: u32,
+    pub then_layer: u8,
+}
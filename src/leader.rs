@@ -0,0 +1,9 @@
+use crate::{combo::ComboAction, keycodes::KC};
+
+/// Keys tapped one after the other after `KC::Leader` produce the output, e.g. `Leader, g, c`
+#[derive(Debug, Clone, Copy)]
+pub struct LeaderSequence {
+    /// Keys of the sequence, without the leader key
+    pub keys: &'static [KC],
+    pub action: ComboAction,
+}
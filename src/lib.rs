@@ -1,37 +1,65 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), no_main)]
 
+#[cfg(not(feature = "std"))]
 pub mod battery;
+#[cfg(not(feature = "std"))]
 pub mod ble;
+pub mod combo;
 pub mod config;
+pub mod engine;
+#[cfg(all(test, feature = "std"))]
+mod fixture;
+pub mod key_override;
+#[cfg(not(feature = "std"))]
 pub mod key_provision;
 pub mod keycodes;
+pub mod layer;
+pub mod leader;
+pub mod macros;
 pub mod matrix;
+#[cfg(not(feature = "std"))]
 pub mod peripherals;
+#[cfg(not(feature = "std"))]
 pub mod storage;
+#[cfg(feature = "std")]
+pub mod trace;
 
-use crate::{config::MATRIX_KEYS_BUFFER, matrix::KeyPos};
-use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, watch::Watch};
+use crate::{config::KEY_EVENT_QUEUE, matrix::KeyEvent};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel, watch::Watch};
 
-/// Shared variable between matrix scan and key provision tasks
-pub static MATRIX_KEYS_LOCAL: Watch<CriticalSectionRawMutex, [KeyPos; MATRIX_KEYS_BUFFER], 2> =
-    Watch::new();
+#[cfg(any(feature = "peripheral", feature = "central"))]
+use core::sync::atomic::AtomicBool;
 
 #[cfg(feature = "peripheral")]
-use usbd_hid::descriptor::KeyboardReport;
+use crate::{config::HID_REPORT_QUEUE, engine::HidReport};
+#[cfg(feature = "peripheral")]
+use embassy_sync::signal::Signal;
+
+/// Queue of key events between the matrix scan (and on the peripheral the split) and key provision tasks
+pub static KEY_EVENTS: Channel<CriticalSectionRawMutex, KeyEvent, KEY_EVENT_QUEUE> = Channel::new();
+
+#[cfg(feature = "peripheral")]
+/// Queue of hid reports between key provision and ble tasks, notified in order
+pub static HID_REPORTS: Channel<CriticalSectionRawMutex, HidReport, HID_REPORT_QUEUE> =
+    Channel::new();
 
 #[cfg(feature = "peripheral")]
-/// Shared variable between ble and key provision tasks
-pub static KEY_REPORT: Watch<CriticalSectionRawMutex, KeyboardReport, 2> = Watch::new();
+/// The hid host is connected, the reports are dropped while it is not
+pub static HOST_CONNECTED: AtomicBool = AtomicBool::new(false);
 
 #[cfg(feature = "peripheral")]
-/// Shared variable between matrix scan and key provision tasks
-pub static MATRIX_KEYS_SPLIT: Watch<CriticalSectionRawMutex, [KeyPos; MATRIX_KEYS_BUFFER], 2> =
-    Watch::new();
+/// The central disconnected, the keys of its half are released
+pub static SPLIT_DISCONNECTED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+#[cfg(feature = "central")]
+/// Queue of key events between key provision and ble tasks, written to the peripheral in order
+pub static MESSAGE_TO_PERI: Channel<CriticalSectionRawMutex, KeyEvent, KEY_EVENT_QUEUE> =
+    Channel::new();
 
 #[cfg(feature = "central")]
-/// Shared variable between ble and key provision tasks
-pub static MESSAGE_TO_PERI: Watch<CriticalSectionRawMutex, [u8; 6], 2> = Watch::new();
+/// The peripheral is connected, the key events are dropped while it is not
+pub static PERI_CONNECTED: AtomicBool = AtomicBool::new(false);
 
 /// Shared variable for battery percentage information
 pub static BATTERY_LEVEL: Watch<CriticalSectionRawMutex, u8, 3> = Watch::new();
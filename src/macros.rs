@@ -0,0 +1,113 @@
+use crate::keycodes::KC;
+
+/// Single step of a macro
+#[derive(Debug, Clone, Copy)]
+pub enum MacroStep {
+    /// Press and release a key
+    Tap(KC),
+    /// Press a key and keep it pressed
+    Press(KC),
+    /// Release a pressed key
+    Release(KC),
+    /// Wait for the given number of milliseconds
+    Delay(u64),
+    /// Type an ascii string (US layout)
+    Type(&'static str),
+}
+
+/// Get the keycode of an ascii character and whether shift is needed (US layout)
+pub fn ascii_to_kc(c: u8) -> Option<(KC, bool)> {
+    let kc = match c {
+        b'a'..=b'z' => (alpha_kc(c - b'a'), false),
+        b'A'..=b'Z' => (alpha_kc(c - b'A'), true),
+        b'1'..=b'9' => (digit_kc(c - b'1'), false),
+        b'0' => (KC::K0, false),
+        b'!' => (KC::K1, true),
+        b'@' => (KC::K2, true),
+        b'#' => (KC::K3, true),
+        b'$' => (KC::K4, true),
+        b'%' => (KC::K5, true),
+        b'^' => (KC::K6, true),
+        b'&' => (KC::K7, true),
+        b'*' => (KC::K8, true),
+        b'(' => (KC::K9, true),
+        b')' => (KC::K0, true),
+        b'\n' => (KC::Enter, false),
+        b'\t' => (KC::Tab, false),
+        b' ' => (KC::Space, false),
+        b'-' => (KC::Dash, false),
+        b'_' => (KC::Dash, true),
+        b'=' => (KC::Equal, false),
+        b'+' => (KC::Equal, true),
+        b'[' => (KC::OpenBracket, false),
+        b'{' => (KC::OpenBracket, true),
+        b']' => (KC::CloseBracket, false),
+        b'}' => (KC::CloseBracket, true),
+        b'\\' => (KC::Bslash, false),
+        b'|' => (KC::Bslash, true),
+        b';' => (KC::SemiColon, false),
+        b':' => (KC::SemiColon, true),
+        b'\'' => (KC::Quote, false),
+        b'"' => (KC::Quote, true),
+        b'`' => (KC::BacktickTilde, false),
+        b'~' => (KC::BacktickTilde, true),
+        b',' => (KC::Comma, false),
+        b'<' => (KC::Comma, true),
+        b'.' => (KC::Period, false),
+        b'>' => (KC::Period, true),
+        b'/' => (KC::Fslash, false),
+        b'?' => (KC::Fslash, true),
+        _ => return None,
+    };
+
+    Some(kc)
+}
+
+fn alpha_kc(index: u8) -> KC {
+    const ALPHA: [KC; 26] = [
+        KC::Aa,
+        KC::Bb,
+        KC::Cc,
+        KC::Dd,
+        KC::Ee,
+        KC::Ff,
+        KC::Gg,
+        KC::Hh,
+        KC::Ii,
+        KC::Jj,
+        KC::Kk,
+        KC::Ll,
+        KC::Mm,
+        KC::Nn,
+        KC::Oo,
+        KC::Pp,
+        KC::Qq,
+        KC::Rr,
+        KC::Ss,
+        KC::Tt,
+        KC::Uu,
+        KC::Vv,
+        KC::Ww,
+        KC::Xx,
+        KC::Yy,
+        KC::Zz,
+    ];
+
+    ALPHA[index as usize]
+}
+
+fn digit_kc(index: u8) -> KC {
+    const DIGITS: [KC; 9] = [
+        KC::K1,
+        KC::K2,
+        KC::K3,
+        KC::K4,
+        KC::K5,
+        KC::K6,
+        KC::K7,
+        KC::K8,
+        KC::K9,
+    ];
+
+    DIGITS[index as usize]
+}
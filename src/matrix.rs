@@ -1,13 +1,22 @@
-use crate::config::{COLS, ENTER_SLEEP_DEBOUNCE, KEY_DEBOUNCE, MATRIX_KEYS_BUFFER, ROWS};
+use crate::config::COLS;
 use crate::keycodes::KC;
-use crate::{MATRIX_KEYS_LOCAL, delay_ms, delay_us};
+#[cfg(not(feature = "std"))]
+use crate::{
+    KEY_EVENTS,
+    config::{ENTER_SLEEP_DEBOUNCE, KEY_DEBOUNCE, MATRIX_KEYS_BUFFER, ROWS},
+    delay_ms, delay_us,
+};
 
+#[cfg(not(feature = "std"))]
 use core::pin::pin;
 #[cfg(feature = "defmt")]
 use defmt::{Format, info};
+#[cfg(not(feature = "std"))]
 use embassy_futures::select::{Either, select, select_slice};
+#[cfg(not(feature = "std"))]
 use embassy_nrf::gpio::{Input, Output};
 use embassy_time::Instant;
+#[cfg(not(feature = "std"))]
 use heapless::Vec;
 
 #[cfg_attr(feature = "defmt", derive(Format))]
@@ -23,12 +32,124 @@ impl KeyPos {
     }
 }
 
+/// Size of a key event sent from the central to the peripheral
+pub const SPLIT_MESSAGE_LEN: usize = 2;
+
+const SPLIT_RELEASED: u8 = 0;
+const SPLIT_PRESSED: u8 = 1;
+const SPLIT_ROLLOVER_OFF: u8 = 2;
+const SPLIT_ROLLOVER_ON: u8 = 3;
+
+/// Change of the pressed keys, sent in the order it was scanned
+#[cfg_attr(feature = "defmt", derive(Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyEvent {
+    /// Key pressed at the given time
+    Pressed(KeyPos, Instant),
+    /// Key released, the time is when it was last seen pressed
+    Released(KeyPos, Instant),
+    /// More keys are pressed on a half than fit in the matrix keys buffer, or not anymore
+    Rollover {
+        split: bool,
+        active: bool,
+        time: Instant,
+    },
+}
+
+impl KeyEvent {
+    /// Encode the event for the split message,
+    /// row and col must be lower than 16 (fit in 4 bits)
+    pub fn to_split_message(&self) -> [u8; SPLIT_MESSAGE_LEN] {
+        match self {
+            Self::Pressed(key_pos, _) => [(key_pos.row << 4) | key_pos.col, SPLIT_PRESSED],
+            Self::Released(key_pos, _) => [(key_pos.row << 4) | key_pos.col, SPLIT_RELEASED],
+            Self::Rollover { active: false, .. } => [255, SPLIT_ROLLOVER_OFF],
+            Self::Rollover { active: true, .. } => [255, SPLIT_ROLLOVER_ON],
+        }
+    }
+
+    /// Decode a split message received at the given time, the col is moved past the local cols
+    pub fn from_split_message(message: &[u8], time: Instant) -> Option<Self> {
+        let [combined_key, kind] = *message else {
+            return None;
+        };
+
+        let key_pos = KeyPos {
+            row: combined_key >> 4,
+            col: (combined_key & 0x0f) + COLS as u8,
+        };
+
+        match kind {
+            SPLIT_PRESSED => Some(Self::Pressed(key_pos, time)),
+            SPLIT_RELEASED => Some(Self::Released(key_pos, time)),
+            SPLIT_ROLLOVER_OFF => Some(Self::Rollover {
+                split: true,
+                active: false,
+                time,
+            }),
+            SPLIT_ROLLOVER_ON => Some(Self::Rollover {
+                split: true,
+                active: true,
+                time,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Time of the event
+    pub fn time(&self) -> Instant {
+        match *self {
+            Self::Pressed(_, time) | Self::Released(_, time) | Self::Rollover { time, .. } => time,
+        }
+    }
+}
+
+/// Line of a key event trace, e.g. `1000 press 1 6` or `1200 rollover split on`
+impl core::fmt::Display for KeyEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Pressed(key_pos, time) => {
+                write!(
+                    f,
+                    "{} press {} {}",
+                    time.as_millis(),
+                    key_pos.row,
+                    key_pos.col
+                )
+            }
+            Self::Released(key_pos, time) => {
+                write!(
+                    f,
+                    "{} release {} {}",
+                    time.as_millis(),
+                    key_pos.row,
+                    key_pos.col
+                )
+            }
+            Self::Rollover {
+                split,
+                active,
+                time,
+            } => write!(
+                f,
+                "{} rollover {} {}",
+                time.as_millis(),
+                if *split { "split" } else { "local" },
+                if *active { "on" } else { "off" }
+            ),
+        }
+    }
+}
+
 #[cfg_attr(feature = "defmt", derive(Format))]
 #[derive(Default, PartialEq, Debug, Clone, Copy)]
 pub enum KeyState {
     #[default]
     Released,
+    /// Pressed, but not provisioned yet
     Pressed,
+    /// Pressed and provisioned
+    Held,
 }
 
 #[cfg_attr(feature = "defmt", derive(Format))]
@@ -46,11 +167,12 @@ impl Default for Key {
             code: KC::default(),
             position: KeyPos::default(),
             state: KeyState::default(),
-            time: Instant::now(),
+            time: Instant::MIN,
         }
     }
 }
 
+#[cfg(not(feature = "std"))]
 #[cfg_attr(feature = "defmt", derive(Format))]
 #[derive(Copy, Clone, PartialEq)]
 struct MatrixKey {
@@ -58,6 +180,7 @@ struct MatrixKey {
     time: Instant,
 }
 
+#[cfg(not(feature = "std"))]
 impl Default for MatrixKey {
     fn default() -> Self {
         Self {
@@ -67,26 +190,27 @@ impl Default for MatrixKey {
     }
 }
 
+#[cfg(not(feature = "std"))]
 pub struct Matrix<'a> {
     rows: [Output<'a>; ROWS],
     cols: [Input<'a>; COLS],
     reg_keys: [MatrixKey; MATRIX_KEYS_BUFFER],
-    keys_to_send_new: [KeyPos; MATRIX_KEYS_BUFFER],
-    keys_to_send_old: [KeyPos; MATRIX_KEYS_BUFFER],
+    /// A pressed key did not fit in reg_keys in the last scan
+    rollover: bool,
 }
 
+#[cfg(not(feature = "std"))]
 impl<'a> Matrix<'a> {
     pub fn init(rows: [Output<'a>; ROWS], cols: [Input<'a>; COLS]) -> Self {
         Self {
             rows,
             cols,
             reg_keys: [MatrixKey::default(); MATRIX_KEYS_BUFFER],
-            keys_to_send_new: [KeyPos::default(); MATRIX_KEYS_BUFFER],
-            keys_to_send_old: [KeyPos::default(); MATRIX_KEYS_BUFFER],
+            rollover: false,
         }
     }
 
-    /// Debounce the registered keys
+    /// Debounce the registered keys, the released keys are sent as events
     async fn debouncer(&mut self) {
         let instant = Instant::now();
 
@@ -98,6 +222,9 @@ impl<'a> Matrix<'a> {
             if instant >= c_key.time + KEY_DEBOUNCE {
                 #[cfg(feature = "defmt")]
                 info!("[debounce] debounced key: {:?}", c_key.keypos);
+                KEY_EVENTS
+                    .send(KeyEvent::Released(c_key.keypos, c_key.time))
+                    .await;
                 c_key.keypos = KeyPos::default();
             }
         }
@@ -105,8 +232,6 @@ impl<'a> Matrix<'a> {
 
     /// Main function for scanning and registering keys
     pub async fn scan(&mut self) {
-        let matrix_keys_sender = MATRIX_KEYS_LOCAL.sender();
-
         loop {
             if self
                 .reg_keys
@@ -145,6 +270,12 @@ impl<'a> Matrix<'a> {
                 }
             }
 
+            // release the keys before registering the new presses, so the events stay in order
+            self.debouncer().await;
+
+            // a pressed key did not fit in reg_keys
+            let mut rollover = false;
+
             // run matrix scan
             for (row_count, row) in self.rows.iter_mut().enumerate() {
                 row.set_high();
@@ -176,7 +307,15 @@ impl<'a> Matrix<'a> {
                                 .position(|&key_pos| key_pos.keypos == KeyPos::default())
                             {
                                 self.reg_keys[index] = new_m_key;
-                            };
+
+                                #[cfg(feature = "defmt")]
+                                info!("[matrix] pressed key: {:?}", new_m_key.keypos);
+                                KEY_EVENTS
+                                    .send(KeyEvent::Pressed(new_m_key.keypos, new_m_key.time))
+                                    .await;
+                            } else {
+                                rollover = true;
+                            }
                         }
                         // update its time
                         else if let Some(index) = self
@@ -196,23 +335,15 @@ impl<'a> Matrix<'a> {
                 delay_us(1000 / ROWS as u64).await;
             }
 
-            // debouncer
-            self.debouncer().await;
-
-            // filter all non defalut KeyPos elements
-            for (index, c_key) in self.reg_keys.iter().enumerate() {
-                self.keys_to_send_new[index] = c_key.keypos;
-            }
-
-            // send the new value
-            if self.keys_to_send_new != self.keys_to_send_old {
-                #[cfg(feature = "defmt")]
-                info!("[matrix] sent keys: {:?}", self.keys_to_send_new);
-
-                // send the keys
-                matrix_keys_sender.send(self.keys_to_send_new);
-
-                self.keys_to_send_old = self.keys_to_send_new;
+            if rollover != self.rollover {
+                KEY_EVENTS
+                    .send(KeyEvent::Rollover {
+                        split: false,
+                        active: rollover,
+                        time: Instant::now(),
+                    })
+                    .await;
+                self.rollover = rollover;
             }
         }
     }
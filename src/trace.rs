@@ -0,0 +1,216 @@
+//! Key event traces, replayed through the engine on the host.
+//!
+//! A trace has a key event per line, `<ms> press <row> <col>`, `<ms> release <row> <col>`
+//! or `<ms> rollover <local|split> <on|off>`, the split half keys have the cols after the local ones.
+//! `<ms> idle` only lets the time pass, empty lines and lines starting with `#` are skipped.
+//! With the `defmt` feature the keyboard logs its key events as `[trace]` lines.
+//!
+//! The replay output has a line per keyboard report, `<ms> mods <modifier> keys <keycodes>`,
+//! in hex. The traces in `traces/` are replayed with the keys of `fixture::trace_config` and
+//! checked against the `.golden` file next to them,
+//! `UPDATE_GOLDEN=1 cargo make test-host` writes the golden files.
+
+use std::{fmt::Write, str::FromStr, str::SplitWhitespace};
+
+use embassy_futures::block_on;
+use embassy_time::{Duration, Instant};
+
+use crate::{
+    engine::{Engine, EngineConfig, HidReport, Host},
+    matrix::{KeyEvent, KeyPos},
+};
+
+/// Host that collects the reports of a replay
+#[derive(Default)]
+pub struct TraceHost {
+    pub reports: Vec<HidReport>,
+    pub bootloader: bool,
+}
+
+impl Host for TraceHost {
+    async fn send(&mut self, report: HidReport) {
+        self.reports.push(report);
+    }
+
+    async fn delay(&mut self, _duration: Duration) {}
+
+    fn enter_bootloader(&mut self) {
+        self.bootloader = true;
+    }
+}
+
+/// Parse a trace line into its time and key event, `None` for an idle line
+pub fn parse_line(line: &str) -> Result<(Instant, Option<KeyEvent>), String> {
+    let mut fields = line.split_whitespace();
+    let time = Instant::from_millis(number(&mut fields, "time")?);
+
+    let event = match next(&mut fields, "event")? {
+        "idle" => None,
+        "press" => Some(KeyEvent::Pressed(key_pos(&mut fields)?, time)),
+        "release" => Some(KeyEvent::Released(key_pos(&mut fields)?, time)),
+        "rollover" => {
+            let split = match next(&mut fields, "half")? {
+                "local" => false,
+                "split" => true,
+                half => return Err(format!("invalid half {half}")),
+            };
+            let active = match next(&mut fields, "state")? {
+                "on" => true,
+                "off" => false,
+                state => return Err(format!("invalid state {state}")),
+            };
+            Some(KeyEvent::Rollover {
+                split,
+                active,
+                time,
+            })
+        }
+        event => return Err(format!("invalid event {event}")),
+    };
+
+    Ok((time, event))
+}
+
+fn next<'a>(fields: &mut SplitWhitespace<'a>, name: &str) -> Result<&'a str, String> {
+    fields.next().ok_or(format!("missing {name}"))
+}
+
+fn number<T: FromStr>(fields: &mut SplitWhitespace, name: &str) -> Result<T, String> {
+    let value = next(fields, name)?;
+    value.parse().map_err(|_| format!("invalid {name} {value}"))
+}
+
+fn key_pos(fields: &mut SplitWhitespace) -> Result<KeyPos, String> {
+    Ok(KeyPos {
+        row: number(fields, "row")?,
+        col: number(fields, "col")?,
+    })
+}
+
+/// Replay a trace through the engine with a config and get its keyboard reports, one per line
+pub fn replay(trace: &str, config: EngineConfig) -> Result<String, String> {
+    let mut engine = Engine::with_config(TraceHost::default(), config);
+    let mut output = String::new();
+
+    for (index, line) in trace.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (time, event) = parse_line(line).map_err(|err| format!("line {}: {err}", index + 1))?;
+
+        // the engine runs the timeouts before the event itself
+        match event {
+            Some(event) => block_on(engine.event(event)),
+            None => block_on(engine.tick(time)),
+        }
+        write_reports(&mut engine, time, &mut output);
+    }
+
+    Ok(output)
+}
+
+/// Write the reports sent since the last call
+fn write_reports(engine: &mut Engine<TraceHost>, time: Instant, output: &mut String) {
+    let host = engine.host_mut();
+
+    for report in host.reports.drain(..) {
+        if let HidReport::Keyboard(report) = report {
+            let _ = write!(
+                output,
+                "{} mods {:02x} keys",
+                time.as_millis(),
+                report.modifier
+            );
+            for keycode in report.keycodes {
+                let _ = write!(output, " {keycode:02x}");
+            }
+            output.push('\n');
+        }
+    }
+
+    if core::mem::take(&mut host.bootloader) {
+        let _ = writeln!(output, "{} bootloader", time.as_millis());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::trace_config;
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn trace_lines_round_trip() {
+        let events = [
+            KeyEvent::Pressed(KeyPos { row: 1, col: 6 }, Instant::from_millis(1000)),
+            KeyEvent::Released(KeyPos { row: 3, col: 7 }, Instant::from_millis(1005)),
+            KeyEvent::Rollover {
+                split: true,
+                active: true,
+                time: Instant::from_millis(1010),
+            },
+        ];
+
+        for event in events {
+            assert_eq!(
+                parse_line(&event.to_string()),
+                Ok((event.time(), Some(event)))
+            );
+        }
+    }
+
+    #[test]
+    fn golden_traces() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("traces");
+        let update = env::var_os("UPDATE_GOLDEN").is_some();
+        let mut failed = Vec::new();
+
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "trace") {
+                continue;
+            }
+
+            let output = replay(&fs::read_to_string(&path).unwrap(), trace_config()).unwrap();
+            let golden_path = path.with_extension("golden");
+
+            if update {
+                fs::write(&golden_path, &output).unwrap();
+                continue;
+            }
+
+            let golden = fs::read_to_string(&golden_path).unwrap_or_else(|err| {
+                panic!(
+                    "{}: {err}, UPDATE_GOLDEN=1 writes it",
+                    golden_path.display()
+                )
+            });
+            if output != golden {
+                println!("{}:", path.display());
+                for line in diff(&golden, &output) {
+                    println!("{line}");
+                }
+                failed.push(path);
+            }
+        }
+
+        assert!(failed.is_empty(), "replay differs from golden: {failed:?}");
+    }
+
+    /// Lines of the golden and the replay output that differ
+    fn diff(golden: &str, output: &str) -> Vec<String> {
+        let golden: Vec<&str> = golden.lines().collect();
+        let output: Vec<&str> = output.lines().collect();
+
+        (0..golden.len().max(output.len()))
+            .filter(|&index| golden.get(index) != output.get(index))
+            .flat_map(|index| {
+                let expected = golden.get(index).map(|line| format!("-{line}"));
+                let actual = output.get(index).map(|line| format!("+{line}"));
+                expected.into_iter().chain(actual)
+            })
+            .collect()
+    }
+}